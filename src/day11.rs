@@ -1,5 +1,17 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashMap;
+use num::{BigUint, CheckedAdd, CheckedMul, FromPrimitive, Integer, Zero};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+const MULTIPLIER: u64 = 2_024;
+
+pub trait StoneNumber: Integer + CheckedMul + FromPrimitive + Clone + Hash {}
+
+impl<T: Integer + CheckedMul + FromPrimitive + Clone + Hash> StoneNumber for T {}
+
+pub type Histogram<T> = HashMap<T, u128>;
 
 #[aoc_generator(day11)]
 fn parse_input(input: &str) -> Vec<u64> {
@@ -9,101 +21,582 @@ fn parse_input(input: &str) -> Vec<u64> {
     parser.parse(input).unwrap()
 }
 
-fn count_stones(initial_stones: &[u64], blink_count: usize) -> usize {
-    let mut result: HashMap<u64, usize> = initial_stones.iter().fold(
+fn digit_count<T: StoneNumber>(stone: &T, base: &T) -> u32 {
+    let mut remainder = stone.clone();
+    let mut digit_count = 1;
+
+    while remainder >= *base {
+        remainder = remainder / base.clone();
+        digit_count += 1;
+    }
+
+    digit_count
+}
+
+fn split_digits<T: StoneNumber>(stone: &T, base: &T) -> Vec<T> {
+    let digit_count = digit_count(stone, base);
+    let split = num::pow(base.clone(), (digit_count / 2) as usize);
+    let (left, right) = stone.div_rem(&split);
+
+    vec![left, right]
+}
+
+pub trait StoneRules<T> {
+    fn blink(&self, stone: &T) -> Option<Vec<T>>;
+}
+
+impl<T, F: Fn(&T) -> Option<Vec<T>>> StoneRules<T> for F {
+    fn blink(&self, stone: &T) -> Option<Vec<T>> {
+        self(stone)
+    }
+}
+
+pub enum Condition<T> {
+    Always,
+    Equals(T),
+    EvenDigitCount { base: T },
+    DivisibleBy(T),
+}
+
+impl<T: StoneNumber> Condition<T> {
+    fn base(&self) -> Option<&T> {
+        match self {
+            Condition::EvenDigitCount { base } => Some(base),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, stone: &T) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Equals(value) => stone == value,
+            Condition::EvenDigitCount { base } => digit_count(stone, base).is_multiple_of(2),
+            Condition::DivisibleBy(divisor) => stone.is_multiple_of(divisor),
+        }
+    }
+}
+
+pub enum Action<T> {
+    Replace(T),
+    SplitDigits { base: T },
+    Multiply(T),
+    Add(T),
+}
+
+impl<T: StoneNumber + CheckedAdd> Action<T> {
+    fn base(&self) -> Option<&T> {
+        match self {
+            Action::SplitDigits { base } => Some(base),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, stone: &T) -> Option<Vec<T>> {
+        match self {
+            Action::Replace(value) => Some(vec![value.clone()]),
+            Action::SplitDigits { base } => Some(split_digits(stone, base)),
+            Action::Multiply(multiplier) => stone.checked_mul(multiplier).map(|stone| vec![stone]),
+            Action::Add(addend) => stone.checked_add(addend).map(|stone| vec![stone]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBase {
+    pub rule: usize,
+}
+
+impl fmt::Display for InvalidBase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rule {} uses a digit base below 2", self.rule)
+    }
+}
+
+impl Error for InvalidBase {}
+
+pub struct RuleSet<T> {
+    rules: Vec<(Condition<T>, Action<T>)>,
+}
+
+impl<T: StoneNumber + CheckedAdd> RuleSet<T> {
+    pub fn new(rules: Vec<(Condition<T>, Action<T>)>) -> Result<Self, InvalidBase> {
+        let two = T::from_u8(2).unwrap();
+
+        for (rule, (condition, action)) in rules.iter().enumerate() {
+            if condition
+                .base()
+                .into_iter()
+                .chain(action.base())
+                .any(|base| *base < two)
+            {
+                return Err(InvalidBase { rule });
+            }
+        }
+
+        Ok(RuleSet { rules })
+    }
+
+    pub fn standard() -> Option<Self> {
+        let ten = T::from_u8(10)?;
+
+        Some(RuleSet {
+            rules: vec![
+                (
+                    Condition::EvenDigitCount { base: ten.clone() },
+                    Action::SplitDigits { base: ten },
+                ),
+                (Condition::Equals(T::zero()), Action::Replace(T::one())),
+                (
+                    Condition::Always,
+                    Action::Multiply(T::from_u64(MULTIPLIER)?),
+                ),
+            ],
+        })
+    }
+}
+
+impl<T: StoneNumber + CheckedAdd> StoneRules<T> for RuleSet<T> {
+    fn blink(&self, stone: &T) -> Option<Vec<T>> {
+        match self
+            .rules
+            .iter()
+            .find(|(condition, _)| condition.matches(stone))
+        {
+            Some((_, action)) => action.apply(stone),
+            None => Some(vec![stone.clone()]),
+        }
+    }
+}
+
+fn initial_histogram<T: StoneNumber>(initial_stones: &[T]) -> Histogram<T> {
+    initial_stones.iter().fold(
         HashMap::with_capacity(initial_stones.len()),
         |mut result, stone| {
-            *result.entry(*stone).or_default() += 1;
+            *result.entry(stone.clone()).or_default() += 1;
             result
         },
-    );
+    )
+}
+
+fn blink_histogram<T: StoneNumber>(
+    rules: &impl StoneRules<T>,
+    histogram: &Histogram<T>,
+) -> Option<Histogram<T>> {
+    let mut result: Histogram<T> = HashMap::with_capacity(histogram.len());
+
+    for (stone, &count) in histogram {
+        for next_stone in rules.blink(stone)? {
+            let next_count = result.entry(next_stone).or_default();
+            *next_count = next_count.checked_add(count)?;
+        }
+    }
+
+    Some(result)
+}
+
+pub fn blink_histograms<T: StoneNumber>(
+    rules: &impl StoneRules<T>,
+    initial_stones: &[T],
+    blink_count: usize,
+) -> Option<Vec<Histogram<T>>> {
+    let mut histograms = Vec::with_capacity(blink_count + 1);
+    histograms.push(initial_histogram(initial_stones));
 
     for _ in 1..=blink_count {
-        result = result
+        let next_histogram = blink_histogram(rules, histograms.last().unwrap())?;
+        histograms.push(next_histogram);
+    }
+
+    Some(histograms)
+}
+
+pub fn count_stones<T: StoneNumber>(
+    rules: &impl StoneRules<T>,
+    initial_stones: &[T],
+    blink_count: usize,
+) -> Option<u128> {
+    (1..=blink_count)
+        .try_fold(initial_histogram(initial_stones), |histogram, _| {
+            blink_histogram(rules, &histogram)
+        })?
+        .values()
+        .try_fold(0u128, |total, count| total.checked_add(*count))
+}
+
+fn process_stone<T: StoneNumber>(
+    rules: &impl StoneRules<T>,
+    stone: &T,
+    remaining_steps: usize,
+    cache: &mut HashMap<(T, usize), u128>,
+) -> Option<u128> {
+    if let Some(result) = cache.get(&(stone.clone(), remaining_steps)) {
+        return Some(*result);
+    }
+
+    if remaining_steps == 0 {
+        return Some(1);
+    }
+
+    let result = rules
+        .blink(stone)?
+        .iter()
+        .try_fold(0u128, |total, next_stone| {
+            total.checked_add(process_stone(
+                rules,
+                next_stone,
+                remaining_steps - 1,
+                cache,
+            )?)
+        })?;
+
+    cache.insert((stone.clone(), remaining_steps), result);
+
+    Some(result)
+}
+
+pub fn count_stones_with_cache<T: StoneNumber>(
+    rules: &impl StoneRules<T>,
+    initial_stones: &[T],
+    blink_count: usize,
+) -> Option<u128> {
+    let mut cache = HashMap::new();
+
+    initial_stones.iter().try_fold(0u128, |total, stone| {
+        total.checked_add(process_stone(rules, stone, blink_count, &mut cache)?)
+    })
+}
+
+#[derive(Clone)]
+struct Matrix {
+    size: usize,
+    modulus: u64,
+    cells: Vec<u64>,
+}
+
+impl Matrix {
+    fn zero(size: usize, modulus: u64) -> Self {
+        Matrix {
+            size,
+            modulus,
+            cells: vec![0; size * size],
+        }
+    }
+
+    fn identity(size: usize, modulus: u64) -> Self {
+        let mut identity = Matrix::zero(size, modulus);
+
+        for index in 0..size {
+            identity.cells[index * size + index] = 1 % modulus;
+        }
+
+        identity
+    }
+
+    fn multiply(&self, other: &Matrix) -> Matrix {
+        let size = self.size;
+        let modulus = self.modulus as u128;
+        let mut result = Matrix::zero(size, self.modulus);
+
+        for row in 0..size {
+            let mut accumulated = vec![0u128; size];
+
+            for middle in 0..size {
+                let left = self.cells[row * size + middle] as u128;
+
+                if left == 0 {
+                    continue;
+                }
+
+                for (column, value) in accumulated.iter_mut().enumerate() {
+                    *value =
+                        (*value + left * other.cells[middle * size + column] as u128) % modulus;
+                }
+            }
+
+            for (column, value) in accumulated.into_iter().enumerate() {
+                result.cells[row * size + column] = value as u64;
+            }
+        }
+
+        result
+    }
+
+    fn pow(&self, mut exponent: u64) -> Matrix {
+        let mut result = Matrix::identity(self.size, self.modulus);
+        let mut base = self.clone();
+
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = result.multiply(&base);
+            }
+
+            exponent /= 2;
+
+            if exponent > 0 {
+                base = base.multiply(&base);
+            }
+        }
+
+        result
+    }
+
+    fn row_sum(&self, row: usize) -> u64 {
+        self.cells[row * self.size..(row + 1) * self.size]
             .iter()
-            .fold(HashMap::new(), |mut result, (&stone, &count)| {
-                let digit_count = stone.checked_ilog10().unwrap_or(0) + 1;
+            .fold(0, |total, value| (total + value) % self.modulus)
+    }
+}
 
-                match (stone, digit_count) {
-                    (stone, num_digits) if num_digits % 2 == 0 => {
-                        let split = 10u64.pow(num_digits / 2);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExploreError {
+    Overflow,
+    TooLarge { max_size: usize },
+}
 
-                        *result.entry(stone / split).or_default() += count;
-                        *result.entry(stone % split).or_default() += count;
-                    }
-                    (0, _) => {
-                        *result.entry(1).or_default() += count;
-                    }
-                    _ => {
-                        *result.entry(stone * 2_024).or_default() += count;
+impl fmt::Display for ExploreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExploreError::Overflow => write!(f, "stone value overflowed"),
+            ExploreError::TooLarge { max_size } => {
+                write!(f, "stone system has more than {max_size} values")
+            }
+        }
+    }
+}
+
+impl Error for ExploreError {}
+
+pub struct StoneSystem<T> {
+    values: Vec<T>,
+    indexes: HashMap<T, usize>,
+    transitions: Vec<Vec<usize>>,
+}
+
+impl<T: StoneNumber> StoneSystem<T> {
+    pub fn explore(
+        rules: &impl StoneRules<T>,
+        initial_stones: &[T],
+        max_size: usize,
+    ) -> Result<Self, ExploreError> {
+        let mut values = Vec::new();
+        let mut indexes = HashMap::new();
+        let mut transitions = Vec::new();
+
+        let mut queue = VecDeque::new();
+
+        for stone in initial_stones {
+            if !indexes.contains_key(stone) {
+                indexes.insert(stone.clone(), values.len());
+                values.push(stone.clone());
+                queue.push_back(stone.clone());
+            }
+        }
+
+        while let Some(stone) = queue.pop_front() {
+            let mut next_indexes = Vec::with_capacity(2);
+
+            for next_stone in rules.blink(&stone).ok_or(ExploreError::Overflow)? {
+                let next_index = match indexes.get(&next_stone) {
+                    Some(index) => *index,
+                    None => {
+                        if values.len() >= max_size {
+                            return Err(ExploreError::TooLarge { max_size });
+                        }
+
+                        indexes.insert(next_stone.clone(), values.len());
+                        values.push(next_stone.clone());
+                        queue.push_back(next_stone);
+
+                        values.len() - 1
                     }
+                };
+
+                next_indexes.push(next_index);
+            }
+
+            transitions.push(next_indexes);
+        }
+
+        Ok(StoneSystem {
+            values,
+            indexes,
+            transitions,
+        })
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn recurrent_values(&self) -> Vec<&T> {
+        let mut current: HashSet<usize> = (0..self.values.len()).collect();
+
+        loop {
+            let next: HashSet<usize> = current
+                .iter()
+                .flat_map(|index| self.transitions[*index].iter().copied())
+                .collect();
+
+            if next.len() == current.len() {
+                break;
+            }
+
+            current = next;
+        }
+
+        let mut recurrent: Vec<usize> = current.into_iter().collect();
+        recurrent.sort_unstable();
+
+        recurrent
+            .into_iter()
+            .map(|index| &self.values[index])
+            .collect()
+    }
+
+    fn transition_matrix(&self, modulus: u64) -> Matrix {
+        let mut matrix = Matrix::zero(self.values.len(), modulus);
+
+        for (from, next_indexes) in self.transitions.iter().enumerate() {
+            for to in next_indexes {
+                let cell = &mut matrix.cells[from * matrix.size + to];
+                *cell = (*cell + 1) % modulus;
+            }
+        }
+
+        matrix
+    }
+
+    fn count_stones_modulo_sparse(
+        &self,
+        initial_stones: &[T],
+        blink_count: u64,
+        modulus: u64,
+    ) -> Option<u64> {
+        let add = |left: u64, right: u64| {
+            let (sum, overflowed) = left.overflowing_add(right);
+
+            if overflowed || sum >= modulus {
+                sum.wrapping_sub(modulus)
+            } else {
+                sum
+            }
+        };
+
+        let mut counts = vec![0; self.values.len()];
+
+        for stone in initial_stones {
+            let index = *self.indexes.get(stone)?;
+            counts[index] = add(counts[index], 1);
+        }
+
+        let mut next_counts = vec![0; counts.len()];
+
+        for _ in 0..blink_count {
+            next_counts.fill(0);
+
+            for (count, next_indexes) in counts.iter().zip(&self.transitions) {
+                for to in next_indexes {
+                    next_counts[*to] = add(next_counts[*to], *count);
                 }
+            }
 
-                result
-            });
+            std::mem::swap(&mut counts, &mut next_counts);
+        }
+
+        Some(counts.into_iter().fold(0, add))
     }
 
-    result.values().sum()
-}
+    fn count_stones_modulo_dense(
+        &self,
+        initial_stones: &[T],
+        blink_count: u64,
+        modulus: u64,
+    ) -> Option<u64> {
+        let matrix = self.transition_matrix(modulus).pow(blink_count);
 
-fn process_stone(
-    stone: u64,
-    remaining_steps: usize,
-    cache: &mut HashMap<(u64, usize), usize>,
-) -> usize {
-    if let Some(result) = cache.get(&(stone, remaining_steps)) {
-        return *result;
+        initial_stones.iter().try_fold(0, |total, stone| {
+            let index = self.indexes.get(stone)?;
+            Some((total + matrix.row_sum(*index)) % modulus)
+        })
     }
 
-    if remaining_steps == 0 {
-        return 1;
+    pub fn count_stones_modulo(
+        &self,
+        initial_stones: &[T],
+        blink_count: u64,
+        modulus: u64,
+    ) -> Option<u64> {
+        if modulus == 0 {
+            return None;
+        }
+
+        let size = self.values.len() as u128;
+        let edges = self.transitions.iter().map(Vec::len).sum::<usize>() as u128;
+        let dense_cost = size.pow(3) * 2 * u128::from(u64::BITS - blink_count.leading_zeros());
+        let sparse_cost = edges * u128::from(blink_count);
+
+        if sparse_cost <= dense_cost {
+            self.count_stones_modulo_sparse(initial_stones, blink_count, modulus)
+        } else {
+            self.count_stones_modulo_dense(initial_stones, blink_count, modulus)
+        }
     }
 
-    let digit_count = stone.checked_ilog10().unwrap_or(0) + 1;
+    pub fn count_stones_exact(&self, initial_stones: &[T], blink_count: u64) -> Option<BigUint> {
+        let mut counts = vec![BigUint::zero(); self.values.len()];
 
-    let result = match (stone, digit_count) {
-        (stone, num_digits) if num_digits % 2 == 0 => {
-            let split = 10u64.pow(num_digits / 2);
-            process_stone(stone / split, remaining_steps - 1, cache)
-                + process_stone(stone % split, remaining_steps - 1, cache)
+        for stone in initial_stones {
+            counts[*self.indexes.get(stone)?] += 1u32;
         }
-        (0, _) => process_stone(1, remaining_steps - 1, cache),
-        _ => process_stone(stone * 2024, remaining_steps - 1, cache),
-    };
 
-    cache.insert((stone, remaining_steps), result);
+        for _ in 0..blink_count {
+            let mut next_counts = vec![BigUint::zero(); counts.len()];
+
+            for (count, next_indexes) in counts.iter().zip(&self.transitions) {
+                for to in next_indexes {
+                    next_counts[*to] += count;
+                }
+            }
 
-    result
+            counts = next_counts;
+        }
+
+        Some(counts.into_iter().sum())
+    }
 }
 
 #[aoc(day11, part1)]
-fn part1(stones: &[u64]) -> usize {
-    count_stones(stones, 25)
+fn part1(stones: &[u64]) -> Option<u128> {
+    count_stones(&RuleSet::standard()?, stones, 25)
 }
 
 #[aoc(day11, part2)]
-fn part2(stones: &[u64]) -> usize {
-    count_stones(stones, 75)
+fn part2(stones: &[u64]) -> Option<u128> {
+    count_stones(&RuleSet::standard()?, stones, 75)
 }
 
 #[aoc(day11, part1, cache)]
-fn part1_with_cache(stones: &[u64]) -> usize {
-    let mut cache = HashMap::new();
-
-    stones
-        .iter()
-        .map(|stone| process_stone(*stone, 25, &mut cache))
-        .sum()
+fn part1_with_cache(stones: &[u64]) -> Option<u128> {
+    count_stones_with_cache(&RuleSet::standard()?, stones, 25)
 }
 
 #[aoc(day11, part2, cache)]
-fn part2_with_cache(stones: &[u64]) -> usize {
-    let mut cache = HashMap::new();
+fn part2_with_cache(stones: &[u64]) -> Option<u128> {
+    count_stones_with_cache(&RuleSet::standard()?, stones, 75)
+}
 
-    stones
-        .iter()
-        .map(|stone| process_stone(*stone, 75, &mut cache))
-        .sum()
+#[aoc(day11, part2, big)]
+fn part2_big(stones: &[u64]) -> Option<u128> {
+    let stones: Vec<BigUint> = stones.iter().map(|stone| BigUint::from(*stone)).collect();
+    count_stones(&RuleSet::standard()?, &stones, 75)
 }
 
 #[cfg(test)]
@@ -114,11 +607,158 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse_input(TEST_INPUT)), 55_312);
+        assert_eq!(part1(&parse_input(TEST_INPUT)), Some(55_312));
     }
 
     #[test]
     fn part1_example_with_cache() {
-        assert_eq!(part1_with_cache(&parse_input(TEST_INPUT)), 55_312);
+        assert_eq!(part1_with_cache(&parse_input(TEST_INPUT)), Some(55_312));
+    }
+
+    #[test]
+    fn histograms_example() {
+        let histograms =
+            blink_histograms(&RuleSet::standard().unwrap(), &parse_input(TEST_INPUT), 2).unwrap();
+
+        assert_eq!(
+            histograms[2],
+            HashMap::from([(253, 1), (0, 1), (2024, 1), (14168, 1)])
+        );
+    }
+
+    #[test]
+    fn overflow_is_detected() {
+        assert_eq!(
+            count_stones::<u16>(&RuleSet::standard().unwrap(), &[125, 17], 25),
+            None
+        );
+        assert_eq!(
+            count_stones::<u128>(&RuleSet::standard().unwrap(), &[125, 17], 25),
+            count_stones(
+                &RuleSet::standard().unwrap(),
+                &[BigUint::from(125u8), BigUint::from(17u8)],
+                25
+            )
+        );
+    }
+
+    #[test]
+    fn closed_system_example() {
+        let system = StoneSystem::explore(&RuleSet::standard().unwrap(), &[0u64], 1_000).unwrap();
+        let modulus = 1_000_000_007;
+
+        assert_eq!(system.len(), 54);
+        assert_eq!(
+            system.count_stones_modulo(&[0], 75, modulus),
+            count_stones(&RuleSet::standard().unwrap(), &[0u64], 75)
+                .map(|count| (count % modulus as u128) as u64)
+        );
+        assert_eq!(
+            system.count_stones_exact(&[0], 75),
+            count_stones(&RuleSet::standard().unwrap(), &[0u64], 75).map(BigUint::from)
+        );
+        assert_eq!(system.count_stones_modulo(&[1], 0, 1), Some(0));
+        assert_eq!(system.count_stones_modulo(&[1], 0, 0), None);
+        assert_eq!(system.count_stones_modulo(&[1], 1_000_000, 0), None);
+        assert_eq!(system.count_stones_modulo(&[1], 0, modulus), Some(1));
+        assert_eq!(system.count_stones_modulo(&[125], 3, modulus), None);
+        assert_eq!(
+            system.count_stones_modulo_dense(&[0, 1], 100_000, modulus),
+            system.count_stones_modulo_sparse(&[0, 1], 100_000, modulus)
+        );
+    }
+
+    #[test]
+    fn recurrent_values_example() {
+        let countdown = |stone: &u64| {
+            Some(if *stone > 2 {
+                vec![stone / 2]
+            } else {
+                vec![(stone + 1) % 3]
+            })
+        };
+        let system = StoneSystem::explore(&countdown, &[12], 10).unwrap();
+
+        assert_eq!(system.values(), &[12, 6, 3, 1, 2, 0]);
+        assert_eq!(system.recurrent_values(), vec![&1, &2, &0]);
+
+        assert_eq!(
+            StoneSystem::explore(&countdown, &[12], 5).map(|_| ()),
+            Err(ExploreError::TooLarge { max_size: 5 })
+        );
+        assert_eq!(
+            StoneSystem::explore(&RuleSet::<u16>::standard().unwrap(), &[125], 1_000).map(|_| ()),
+            Err(ExploreError::Overflow)
+        );
+    }
+
+    #[test]
+    fn large_closed_system() {
+        let stones = parse_input("773 79858 0 71 213357 2937 1 3998391");
+        let system = StoneSystem::explore(&RuleSet::standard().unwrap(), &stones, 10_000).unwrap();
+        let modulus = 998_244_353;
+
+        assert_eq!(system.len(), 3_870);
+
+        let exact = system.count_stones_exact(&stones, 300).unwrap();
+
+        assert_eq!(
+            system.count_stones_modulo(&stones, 300, modulus),
+            Some((exact % modulus).try_into().unwrap())
+        );
+        assert_eq!(
+            system.count_stones_exact(&stones, 75),
+            count_stones(&RuleSet::standard().unwrap(), &stones, 75).map(BigUint::from)
+        );
+    }
+
+    #[test]
+    fn rule_set_validation() {
+        assert!(RuleSet::<u8>::standard().is_none());
+        assert!(RuleSet::<i16>::standard().is_some());
+        assert_eq!(
+            RuleSet::new(vec![
+                (Condition::Equals(0u64), Action::Replace(1)),
+                (Condition::EvenDigitCount { base: 1 }, Action::Multiply(2)),
+            ])
+            .map(|_| ()),
+            Err(InvalidBase { rule: 1 })
+        );
+        assert_eq!(
+            RuleSet::new(vec![(
+                Condition::Always,
+                Action::SplitDigits { base: 0i64 }
+            )])
+            .map(|_| ()),
+            Err(InvalidBase { rule: 0 })
+        );
+    }
+
+    #[test]
+    fn custom_rules() {
+        let binary_rules = RuleSet::new(vec![
+            (
+                Condition::EvenDigitCount { base: 2u64 },
+                Action::SplitDigits { base: 2 },
+            ),
+            (Condition::Equals(0), Action::Replace(1)),
+            (Condition::Always, Action::Multiply(3)),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            blink_histograms(&binary_rules, &[0, 1, 3], 3).unwrap()[3],
+            HashMap::from([(1, 6), (3, 2)])
+        );
+
+        let halving = |stone: &u64| {
+            Some(if stone.is_multiple_of(&2) {
+                vec![stone / 2]
+            } else {
+                vec![*stone, *stone]
+            })
+        };
+
+        assert_eq!(count_stones(&halving, &[8], 5), Some(4));
     }
 }
@@ -1,3 +1,4 @@
+use crate::disjoint_set::DisjointSet;
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::{HashMap, HashSet, VecDeque};
 
@@ -171,6 +172,238 @@ fn part2(map: &[Vec<char>]) -> usize {
     result
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub label: char,
+    pub cells: Vec<(usize, usize)>,
+    pub area: usize,
+    pub perimeter: usize,
+    pub sides: usize,
+    pub bounding_box: ((usize, usize), (usize, usize)),
+    pub holes: usize,
+    pub enclosing_region: Option<usize>,
+}
+
+impl Region {
+    pub fn price(&self) -> usize {
+        self.area * self.perimeter
+    }
+
+    pub fn discounted_price(&self) -> usize {
+        self.area * self.sides
+    }
+}
+
+fn region_ids(map: &[Vec<char>]) -> (Vec<Vec<usize>>, usize) {
+    let (width, height) = (map[0].len(), map.len());
+    let mut plots = DisjointSet::new(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            if x + 1 < width && map[y][x + 1] == map[y][x] {
+                plots.union(y * width + x, y * width + x + 1);
+            }
+
+            if y + 1 < height && map[y + 1][x] == map[y][x] {
+                plots.union(y * width + x, (y + 1) * width + x);
+            }
+        }
+    }
+
+    let mut ids_by_root = HashMap::new();
+    let mut ids = vec![vec![0; width]; height];
+
+    for (y, row) in ids.iter_mut().enumerate() {
+        for (x, id) in row.iter_mut().enumerate() {
+            let next_id = ids_by_root.len();
+            *id = *ids_by_root
+                .entry(plots.find(y * width + x))
+                .or_insert(next_id);
+        }
+    }
+
+    (ids, ids_by_root.len())
+}
+
+fn enclosing_regions(adjacency: &[Vec<usize>], outside: usize) -> Vec<Option<usize>> {
+    let mut discovery = vec![usize::MAX; adjacency.len()];
+    let mut low = vec![usize::MAX; adjacency.len()];
+    let mut parents = vec![outside; adjacency.len()];
+    let mut order = vec![outside];
+    let mut stack = vec![(outside, 0)];
+
+    discovery[outside] = 0;
+    low[outside] = 0;
+
+    while let Some((node, index)) = stack.pop() {
+        if let Some(&neighbour) = adjacency[node].get(index) {
+            stack.push((node, index + 1));
+
+            if discovery[neighbour] == usize::MAX {
+                discovery[neighbour] = order.len();
+                low[neighbour] = order.len();
+                parents[neighbour] = node;
+                order.push(neighbour);
+                stack.push((neighbour, 0));
+            } else if neighbour != parents[node] {
+                low[node] = low[node].min(discovery[neighbour]);
+            }
+        } else if node != outside {
+            let parent = parents[node];
+            low[parent] = low[parent].min(low[node]);
+        }
+    }
+
+    let mut enclosing = vec![None; adjacency.len()];
+
+    for node in order.into_iter().skip(1) {
+        let parent = parents[node];
+
+        enclosing[node] = if parent == outside {
+            None
+        } else if low[node] >= discovery[parent] {
+            Some(parent)
+        } else {
+            enclosing[parent]
+        };
+    }
+
+    enclosing
+}
+
+pub fn analyse_regions(map: &[Vec<char>]) -> Vec<Region> {
+    let (width, height) = (map[0].len(), map.len());
+    let (ids, region_count) = region_ids(map);
+
+    let mut regions: Vec<Region> = Vec::with_capacity(region_count);
+
+    for y in 0..height {
+        for x in 0..width {
+            let id = ids[y][x];
+
+            if id == regions.len() {
+                regions.push(Region {
+                    label: map[y][x],
+                    cells: Vec::new(),
+                    area: 0,
+                    perimeter: 0,
+                    sides: 0,
+                    bounding_box: ((x, y), (x, y)),
+                    holes: 0,
+                    enclosing_region: None,
+                });
+            }
+
+            let region = &mut regions[id];
+            let ((min_x, min_y), (max_x, max_y)) = &mut region.bounding_box;
+
+            region.cells.push((x, y));
+            region.area += 1;
+            *min_x = (*min_x).min(x);
+            *min_y = (*min_y).min(y);
+            *max_x = (*max_x).max(x);
+            *max_y = (*max_y).max(y);
+
+            region.perimeter += [
+                x > 0 && ids[y][x - 1] == id,
+                x + 1 < width && ids[y][x + 1] == id,
+                y > 0 && ids[y - 1][x] == id,
+                y + 1 < height && ids[y + 1][x] == id,
+            ]
+            .iter()
+            .filter(|same_region| !**same_region)
+            .count();
+        }
+    }
+
+    let id_at = |x: i32, y: i32| {
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            None
+        } else {
+            Some(ids[y as usize][x as usize])
+        }
+    };
+
+    let mut euler_numbers = vec![0i64; region_count];
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); region_count + 1];
+
+    for y in -1..height as i32 {
+        for x in -1..width as i32 {
+            let quad = [
+                id_at(x, y),
+                id_at(x + 1, y),
+                id_at(x, y + 1),
+                id_at(x + 1, y + 1),
+            ];
+
+            for (index, id) in quad.iter().enumerate() {
+                for other in &quad[..index] {
+                    if other != id {
+                        let (id, other) =
+                            (id.unwrap_or(region_count), other.unwrap_or(region_count));
+
+                        adjacency[id].push(other);
+                        adjacency[other].push(id);
+                    }
+                }
+
+                let Some(id) = id else {
+                    continue;
+                };
+
+                if quad[..index].contains(&Some(*id)) {
+                    continue;
+                }
+
+                let members = quad.map(|other| other == Some(*id));
+
+                match members.iter().filter(|member| **member).count() {
+                    1 => {
+                        regions[*id].sides += 1;
+                        euler_numbers[*id] += 1;
+                    }
+                    2 if members[0] == members[3] => {
+                        regions[*id].sides += 2;
+                        euler_numbers[*id] += 2;
+                    }
+                    3 => {
+                        regions[*id].sides += 1;
+                        euler_numbers[*id] -= 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for neighbours in adjacency.iter_mut() {
+        neighbours.sort_unstable();
+        neighbours.dedup();
+    }
+
+    let enclosing = enclosing_regions(&adjacency, region_count);
+
+    for (id, region) in regions.iter_mut().enumerate() {
+        region.holes = (1 - euler_numbers[id] / 4) as usize;
+        region.enclosing_region = enclosing[id];
+    }
+
+    regions
+}
+
+#[aoc(day12, part1, union_find)]
+fn part1_union_find(map: &[Vec<char>]) -> usize {
+    analyse_regions(map).iter().map(Region::price).sum()
+}
+
+#[aoc(day12, part2, union_find)]
+fn part2_union_find(map: &[Vec<char>]) -> usize {
+    analyse_regions(map)
+        .iter()
+        .map(Region::discounted_price)
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,4 +482,91 @@ AAAAAA";
     fn part2_example_5() {
         assert_eq!(part2(&parse_input(TEST_INPUT_3)), 1206);
     }
+
+    #[test]
+    fn union_find_examples() {
+        for (input, price, discounted_price) in [
+            (TEST_INPUT_1, 140, 80),
+            (TEST_INPUT_2, 772, 436),
+            (TEST_INPUT_3, 1930, 1206),
+            (TEST_INPUT_4, 692, 236),
+            (TEST_INPUT_5, 1184, 368),
+        ] {
+            let map = parse_input(input);
+
+            assert_eq!(part1_union_find(&map), price);
+            assert_eq!(part2_union_find(&map), discounted_price);
+        }
+    }
+
+    #[test]
+    fn region_geometry() {
+        let regions = analyse_regions(&parse_input(TEST_INPUT_2));
+        let outer = &regions[0];
+
+        assert_eq!(regions.len(), 5);
+        assert_eq!(outer.label, 'O');
+        assert_eq!(outer.area, 21);
+        assert_eq!(outer.perimeter, 36);
+        assert_eq!(outer.sides, 20);
+        assert_eq!(outer.bounding_box, ((0, 0), (4, 4)));
+        assert_eq!(outer.holes, 4);
+        assert_eq!(outer.enclosing_region, None);
+
+        assert!(regions[1..]
+            .iter()
+            .all(|region| region.label == 'X' && region.enclosing_region == Some(0)));
+    }
+
+    #[test]
+    fn nested_regions() {
+        let regions = analyse_regions(&parse_input("AAAAA\nABBBA\nABCBA\nABBBA\nAAAAA"));
+
+        assert_eq!(
+            regions
+                .iter()
+                .map(|region| (region.label, region.holes, region.enclosing_region))
+                .collect::<Vec<_>>(),
+            vec![('A', 1, None), ('B', 1, Some(0)), ('C', 0, Some(1))]
+        );
+    }
+
+    #[test]
+    fn diagonal_escapes() {
+        let summary = |map: &str| {
+            analyse_regions(&parse_input(map))
+                .iter()
+                .map(|region| (region.label, region.holes, region.enclosing_region))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            summary("AAAA\nABBA\nABBA\nAAAC"),
+            vec![('A', 0, None), ('B', 0, None), ('C', 0, None)]
+        );
+        assert_eq!(
+            summary("AAAAAAA\nABBBBBA\nABCCDBA\nABCCDBA\nABBBBBA\nAAAAAAA"),
+            vec![
+                ('A', 1, None),
+                ('B', 1, Some(0)),
+                ('C', 0, Some(1)),
+                ('D', 0, Some(1))
+            ]
+        );
+        assert_eq!(
+            summary("AAAAA\nAB.BA\nA.C.A\nAB.BA\nAAAAA"),
+            vec![
+                ('A', 1, None),
+                ('B', 0, Some(0)),
+                ('.', 0, Some(0)),
+                ('B', 0, Some(0)),
+                ('.', 0, Some(0)),
+                ('C', 0, Some(0)),
+                ('.', 0, Some(0)),
+                ('B', 0, Some(0)),
+                ('.', 0, Some(0)),
+                ('B', 0, Some(0))
+            ]
+        );
+    }
 }
@@ -1,4 +1,8 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use num::integer::{ExtendedGcd, Integer};
+use num::{BigInt, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, FromPrimitive, Signed};
+use std::error::Error;
+use std::fmt;
 
 const MAX_TIMES_PRESSED: i64 = 100;
 const PUSH_A_COST: i64 = 3;
@@ -29,64 +33,448 @@ fn parse_input(input: &str) -> Vec<MachineSetup> {
     parser.parse(input).unwrap()
 }
 
-fn solve_machine(
-    MachineSetup {
-        button_a,
-        button_b,
-        prize_location,
-    }: &MachineSetup,
-    with_limit: bool,
-) -> Option<i64> {
-    let a_nom = prize_location.0 * button_b.1 - prize_location.1 * button_b.0;
-    let b_nom = button_a.0 * prize_location.1 - button_a.1 * prize_location.0;
-    let denom = button_a.0 * button_b.1 - button_a.1 * button_b.0;
-
-    if a_nom % denom == 0 && b_nom % denom == 0 {
-        let a_pressed = a_nom / denom;
-        let b_pressed = b_nom / denom;
-
-        if !with_limit || a_pressed <= MAX_TIMES_PRESSED && b_pressed <= MAX_TIMES_PRESSED {
-            Some(a_pressed * PUSH_A_COST + b_pressed * PUSH_B_COST)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "arithmetic overflow")
+    }
+}
+
+impl Error for Overflow {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveError {
+    Overflow,
+    Unbounded,
+}
+
+impl From<Overflow> for SolveError {
+    fn from(_: Overflow) -> Self {
+        SolveError::Overflow
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Overflow => write!(f, "{}", Overflow),
+            SolveError::Unbounded => {
+                write!(f, "cannot bound button presses without a press limit")
+            }
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+pub trait Coordinate:
+    Integer + Signed + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + FromPrimitive + Clone
+{
+}
+
+impl<T> Coordinate for T where
+    T: Integer + Signed + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + FromPrimitive + Clone
+{
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Button<T> {
+    pub movement: (T, T),
+    pub cost: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClawMachine<T> {
+    pub buttons: Vec<Button<T>>,
+    pub prize_location: (T, T),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution<T> {
+    pub presses: Vec<T>,
+    pub cost: T,
+}
+
+fn convert<T: Coordinate>(value: i64) -> Result<T, Overflow> {
+    T::from_i64(value).ok_or(Overflow)
+}
+
+fn add<T: Coordinate>(left: &T, right: &T) -> Result<T, Overflow> {
+    left.checked_add(right).ok_or(Overflow)
+}
+
+fn sub<T: Coordinate>(left: &T, right: &T) -> Result<T, Overflow> {
+    left.checked_sub(right).ok_or(Overflow)
+}
+
+fn mul<T: Coordinate>(left: &T, right: &T) -> Result<T, Overflow> {
+    left.checked_mul(right).ok_or(Overflow)
+}
+
+fn div_rem<T: Coordinate>(nom: &T, denom: &T) -> Result<(T, T), Overflow> {
+    let quotient = nom.checked_div(denom).ok_or(Overflow)?;
+    let remainder = sub(nom, &mul(&quotient, denom)?)?;
+
+    Ok((quotient, remainder))
+}
+
+fn div_exact<T: Coordinate>(nom: &T, denom: &T) -> Result<Option<T>, Overflow> {
+    let (quotient, remainder) = div_rem(nom, denom)?;
+
+    Ok(remainder.is_zero().then_some(quotient))
+}
+
+fn div_floor<T: Coordinate>(nom: &T, denom: &T) -> Result<T, Overflow> {
+    let (quotient, remainder) = div_rem(nom, denom)?;
+
+    if !remainder.is_zero() && remainder.is_negative() != denom.is_negative() {
+        sub(&quotient, &T::one())
+    } else {
+        Ok(quotient)
+    }
+}
+
+fn div_ceil<T: Coordinate>(nom: &T, denom: &T) -> Result<T, Overflow> {
+    let (quotient, remainder) = div_rem(nom, denom)?;
+
+    if !remainder.is_zero() && remainder.is_negative() == denom.is_negative() {
+        add(&quotient, &T::one())
+    } else {
+        Ok(quotient)
+    }
+}
+
+fn cross<T: Coordinate>(first: &(T, T), second: &(T, T)) -> Result<T, Overflow> {
+    sub(&mul(&first.0, &second.1)?, &mul(&first.1, &second.0)?)
+}
+
+fn scale<T: Coordinate>(presses: &T, movement: &(T, T)) -> Result<(T, T), Overflow> {
+    Ok((mul(presses, &movement.0)?, mul(presses, &movement.1)?))
+}
+
+fn translate<T: Coordinate>(first: &(T, T), second: &(T, T)) -> Result<(T, T), Overflow> {
+    Ok((add(&first.0, &second.0)?, add(&first.1, &second.1)?))
+}
+
+impl MachineSetup {
+    fn claw_machine<T: Coordinate>(&self) -> Result<ClawMachine<T>, Overflow> {
+        Ok(ClawMachine {
+            buttons: vec![
+                Button {
+                    movement: (convert(self.button_a.0)?, convert(self.button_a.1)?),
+                    cost: convert(PUSH_A_COST)?,
+                },
+                Button {
+                    movement: (convert(self.button_b.0)?, convert(self.button_b.1)?),
+                    cost: convert(PUSH_B_COST)?,
+                },
+            ],
+            prize_location: (
+                convert(self.prize_location.0)?,
+                convert(self.prize_location.1)?,
+            ),
+        })
+    }
+}
+
+impl<T: Coordinate> ClawMachine<T> {
+    pub fn with_offset(&self, offset: &T) -> Result<Self, Overflow> {
+        Ok(ClawMachine {
+            buttons: self.buttons.clone(),
+            prize_location: translate(&self.prize_location, &(offset.clone(), offset.clone()))?,
+        })
+    }
+
+    pub fn solve(&self, max_presses: Option<T>) -> Result<Option<Solution<T>>, SolveError> {
+        solve_buttons(&self.buttons, &self.prize_location, max_presses.as_ref())
+    }
+}
+
+fn within_limits<T: Coordinate>(presses: &T, max_presses: Option<&T>) -> bool {
+    !presses.is_negative() && max_presses.is_none_or(|max_presses| presses <= max_presses)
+}
+
+type StepBounds<T> = (Option<T>, Option<T>);
+
+fn constrain_steps<T: Coordinate>(
+    (start, step): (&T, &T),
+    max_presses: Option<&T>,
+    (lower, upper): StepBounds<T>,
+) -> Result<Option<StepBounds<T>>, Overflow> {
+    if step.is_zero() {
+        return Ok(within_limits(start, max_presses).then_some((lower, upper)));
+    }
+
+    let from_zero = sub(&T::zero(), start)?;
+    let to_limit = max_presses
+        .map(|max_presses| sub(max_presses, start))
+        .transpose()?;
+
+    let (low_bound, high_bound) = if step.is_positive() {
+        (
+            Some(div_ceil(&from_zero, step)?),
+            to_limit
+                .map(|to_limit| div_floor(&to_limit, step))
+                .transpose()?,
+        )
+    } else {
+        (
+            to_limit
+                .map(|to_limit| div_ceil(&to_limit, step))
+                .transpose()?,
+            Some(div_floor(&from_zero, step)?),
+        )
+    };
+
+    let upper = match (upper, high_bound) {
+        (Some(upper), Some(high_bound)) => Some(upper.min(high_bound)),
+        (upper, high_bound) => upper.or(high_bound),
+    };
+
+    Ok(Some((lower.max(low_bound), upper)))
+}
+
+fn solve_single_button<T: Coordinate>(
+    button: &Button<T>,
+    prize_location: &(T, T),
+    max_presses: Option<&T>,
+) -> Result<Option<Solution<T>>, Overflow> {
+    let presses = if !button.movement.0.is_zero() {
+        div_exact(&prize_location.0, &button.movement.0)?
+    } else if !button.movement.1.is_zero() {
+        div_exact(&prize_location.1, &button.movement.1)?
+    } else {
+        Some(T::zero())
+    };
+
+    let Some(presses) = presses else {
+        return Ok(None);
+    };
+
+    if !within_limits(&presses, max_presses)
+        || scale(&presses, &button.movement)? != *prize_location
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(Solution {
+        cost: mul(&presses, &button.cost)?,
+        presses: vec![presses],
+    }))
+}
+
+fn solve_two_buttons<T: Coordinate>(
+    first: &Button<T>,
+    second: &Button<T>,
+    prize_location: &(T, T),
+    max_presses: Option<&T>,
+) -> Result<Option<Solution<T>>, SolveError> {
+    let (button_a, button_b) = (&first.movement, &second.movement);
+    let denom = cross(button_a, button_b)?;
+
+    let (a_pressed, b_pressed) = if !denom.is_zero() {
+        let a_nom = cross(prize_location, button_b)?;
+        let b_nom = cross(button_a, prize_location)?;
+
+        match (div_exact(&a_nom, &denom)?, div_exact(&b_nom, &denom)?) {
+            (Some(a_pressed), Some(b_pressed)) => (a_pressed, b_pressed),
+            _ => return Ok(None),
+        }
+    } else {
+        let (a_movement, b_movement, distance) = if !button_a.0.is_zero() || !button_b.0.is_zero() {
+            (&button_a.0, &button_b.0, &prize_location.0)
         } else {
-            None
+            (&button_a.1, &button_b.1, &prize_location.1)
+        };
+
+        if a_movement.is_zero() && b_movement.is_zero() {
+            return Ok(
+                (prize_location.0.is_zero() && prize_location.1.is_zero()).then(|| Solution {
+                    presses: vec![T::zero(), T::zero()],
+                    cost: T::zero(),
+                }),
+            );
         }
+
+        let ExtendedGcd { gcd, x, y, .. } = a_movement.extended_gcd(b_movement);
+
+        let Some(multiple) = div_exact(distance, &gcd)? else {
+            return Ok(None);
+        };
+
+        let (a_start, b_start) = (mul(&x, &multiple)?, mul(&y, &multiple)?);
+        let (a_step, b_step) = (
+            div_rem(b_movement, &gcd)?.0,
+            sub(&T::zero(), &div_rem(a_movement, &gcd)?.0)?,
+        );
+
+        let Some(bounds) = constrain_steps((&a_start, &a_step), max_presses, (None, None))? else {
+            return Ok(None);
+        };
+        let Some((lower, upper)) = constrain_steps((&b_start, &b_step), max_presses, bounds)?
+        else {
+            return Ok(None);
+        };
+
+        if let (Some(lower), Some(upper)) = (&lower, &upper) {
+            if lower > upper {
+                return Ok(None);
+            }
+        }
+
+        let slope = add(&mul(&a_step, &first.cost)?, &mul(&b_step, &second.cost)?)?;
+
+        let steps = if slope.is_positive() {
+            lower
+        } else if slope.is_negative() {
+            upper
+        } else {
+            Some(lower.or(upper).unwrap_or_else(T::zero))
+        };
+
+        let Some(steps) = steps else {
+            return Err(SolveError::Unbounded);
+        };
+
+        (
+            add(&a_start, &mul(&steps, &a_step)?)?,
+            add(&b_start, &mul(&steps, &b_step)?)?,
+        )
+    };
+
+    if !within_limits(&a_pressed, max_presses)
+        || !within_limits(&b_pressed, max_presses)
+        || translate(&scale(&a_pressed, button_a)?, &scale(&b_pressed, button_b)?)?
+            != *prize_location
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(Solution {
+        cost: add(
+            &mul(&a_pressed, &first.cost)?,
+            &mul(&b_pressed, &second.cost)?,
+        )?,
+        presses: vec![a_pressed, b_pressed],
+    }))
+}
+
+fn press_bound<T: Coordinate>(
+    button: &Button<T>,
+    buttons: &[Button<T>],
+    prize_location: &(T, T),
+    max_presses: Option<&T>,
+) -> Result<T, SolveError> {
+    let x_bound = if button.movement.0.is_positive()
+        && buttons.iter().all(|other| !other.movement.0.is_negative())
+    {
+        Some(div_floor(&prize_location.0, &button.movement.0)?)
     } else {
         None
+    };
+
+    let y_bound = if button.movement.1.is_positive()
+        && buttons.iter().all(|other| !other.movement.1.is_negative())
+    {
+        Some(div_floor(&prize_location.1, &button.movement.1)?)
+    } else {
+        None
+    };
+
+    [x_bound, y_bound, max_presses.cloned()]
+        .into_iter()
+        .flatten()
+        .min()
+        .ok_or(SolveError::Unbounded)
+}
+
+fn solve_buttons<T: Coordinate>(
+    buttons: &[Button<T>],
+    prize_location: &(T, T),
+    max_presses: Option<&T>,
+) -> Result<Option<Solution<T>>, SolveError> {
+    match buttons {
+        [] => Ok(
+            (prize_location.0.is_zero() && prize_location.1.is_zero()).then(|| Solution {
+                presses: Vec::new(),
+                cost: T::zero(),
+            }),
+        ),
+        [button] => Ok(solve_single_button(button, prize_location, max_presses)?),
+        [first, second] => solve_two_buttons(first, second, prize_location, max_presses),
+        [first, rest @ ..] => {
+            let bound = press_bound(first, buttons, prize_location, max_presses)?;
+
+            let mut best_solution: Option<Solution<T>> = None;
+            let mut presses = T::zero();
+
+            while presses <= bound {
+                let (moved_x, moved_y) = scale(&presses, &first.movement)?;
+                let remaining = (
+                    sub(&prize_location.0, &moved_x)?,
+                    sub(&prize_location.1, &moved_y)?,
+                );
+
+                if let Some(mut solution) = solve_buttons(rest, &remaining, max_presses)? {
+                    solution.cost = add(&solution.cost, &mul(&presses, &first.cost)?)?;
+                    solution.presses.insert(0, presses.clone());
+
+                    if best_solution
+                        .as_ref()
+                        .is_none_or(|best_solution| solution.cost < best_solution.cost)
+                    {
+                        best_solution = Some(solution);
+                    }
+                }
+
+                presses = add(&presses, &T::one())?;
+            }
+
+            Ok(best_solution)
+        }
     }
 }
 
+fn total_cost<T: Coordinate>(
+    machines: &[MachineSetup],
+    offset: i64,
+    max_presses: Option<i64>,
+) -> Result<T, SolveError> {
+    let offset = convert(offset)?;
+    let max_presses = max_presses.map(convert).transpose()?;
+
+    machines.iter().try_fold(T::zero(), |total, machine_setup| {
+        match machine_setup
+            .claw_machine()?
+            .with_offset(&offset)?
+            .solve(max_presses.clone())?
+        {
+            Some(solution) => Ok(add(&total, &solution.cost)?),
+            None => Ok(total),
+        }
+    })
+}
+
 #[aoc(day13, part1)]
-fn part1(machines: &[MachineSetup]) -> i64 {
-    machines
-        .iter()
-        .filter_map(|machine_setup| solve_machine(machine_setup, true))
-        .sum()
+fn part1(machines: &[MachineSetup]) -> Result<i64, SolveError> {
+    total_cost(machines, 0, Some(MAX_TIMES_PRESSED))
 }
 
 #[aoc(day13, part2)]
-fn part2(machines: &[MachineSetup]) -> i64 {
-    machines
-        .iter()
-        .filter_map(
-            |MachineSetup {
-                 button_a,
-                 button_b,
-                 prize_location,
-             }: &MachineSetup| {
-                solve_machine(
-                    &MachineSetup {
-                        button_a: *button_a,
-                        button_b: *button_b,
-                        prize_location: (
-                            prize_location.0 + EXTRA_DISTANCE,
-                            prize_location.1 + EXTRA_DISTANCE,
-                        ),
-                    },
-                    false,
-                )
-            },
-        )
-        .sum()
+fn part2(machines: &[MachineSetup]) -> Result<i64, SolveError> {
+    total_cost(machines, EXTRA_DISTANCE, None)
+}
+
+#[aoc(day13, part2, i128)]
+fn part2_i128(machines: &[MachineSetup]) -> Result<i128, SolveError> {
+    total_cost(machines, EXTRA_DISTANCE, None)
+}
+
+#[aoc(day13, part2, big)]
+fn part2_big(machines: &[MachineSetup]) -> Result<BigInt, SolveError> {
+    total_cost(machines, EXTRA_DISTANCE, None)
 }
 
 #[cfg(test)]
@@ -111,6 +499,131 @@ Prize: X=18641, Y=10279";
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse_input(TEST_INPUT)), 480);
+        assert_eq!(part1(&parse_input(TEST_INPUT)), Ok(480));
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2(&parse_input(TEST_INPUT)), Ok(875_318_608_908));
+    }
+
+    #[test]
+    fn collinear_buttons() {
+        let machine = |prize_location: (i64, i64)| ClawMachine {
+            buttons: vec![
+                Button {
+                    movement: (2, 2),
+                    cost: 3,
+                },
+                Button {
+                    movement: (6, 6),
+                    cost: 1,
+                },
+            ],
+            prize_location,
+        };
+
+        assert_eq!(
+            machine((20, 20)).solve(None),
+            Ok(Some(Solution {
+                presses: vec![1, 3],
+                cost: 6
+            }))
+        );
+        assert_eq!(machine((20, 20)).solve(Some(2)), Ok(None));
+        assert_eq!(machine((7, 7)).solve(None), Ok(None));
+        assert_eq!(machine((20, 21)).solve(None), Ok(None));
+
+        let rebate = ClawMachine {
+            buttons: vec![
+                Button {
+                    movement: (2, 2),
+                    cost: 1,
+                },
+                Button {
+                    movement: (-1, -1),
+                    cost: -3,
+                },
+            ],
+            prize_location: (3, 3),
+        };
+
+        assert_eq!(rebate.solve(None), Err(SolveError::Unbounded));
+        assert_eq!(
+            rebate.solve(Some(4)),
+            Ok(Some(Solution {
+                presses: vec![3, 3],
+                cost: -6
+            }))
+        );
+    }
+
+    #[test]
+    fn three_buttons() {
+        let machine: ClawMachine<i64> = ClawMachine {
+            buttons: vec![
+                Button {
+                    movement: (1, 0),
+                    cost: 1,
+                },
+                Button {
+                    movement: (0, 1),
+                    cost: 1,
+                },
+                Button {
+                    movement: (1, 1),
+                    cost: 1,
+                },
+            ],
+            prize_location: (3, 2),
+        };
+
+        assert_eq!(
+            machine.solve(None),
+            Ok(Some(Solution {
+                presses: vec![1, 0, 2],
+                cost: 3
+            }))
+        );
+
+        let mixed_signs = ClawMachine {
+            buttons: vec![
+                Button {
+                    movement: (2, 0),
+                    cost: 1,
+                },
+                Button {
+                    movement: (-1, 0),
+                    cost: 1,
+                },
+                Button {
+                    movement: (0, 1),
+                    cost: 1,
+                },
+            ],
+            prize_location: (1, 1),
+        };
+
+        assert_eq!(mixed_signs.solve(None), Err(SolveError::Unbounded));
+        assert_eq!(
+            mixed_signs.solve(Some(5)),
+            Ok(Some(Solution {
+                presses: vec![1, 1, 1],
+                cost: 3
+            }))
+        );
+    }
+
+    #[test]
+    fn wide_integer_types() {
+        let machines = parse_input(TEST_INPUT);
+
+        assert_eq!(part2_i128(&machines), Ok(875_318_608_908));
+        assert_eq!(part2_big(&machines), Ok(BigInt::from(875_318_608_908i64)));
+        assert_eq!(
+            total_cost::<i64>(&machines, i64::MAX / 10, None),
+            Err(SolveError::Overflow)
+        );
+        assert!(total_cost::<i128>(&machines, i64::MAX / 10, None).is_ok());
     }
 }
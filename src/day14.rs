@@ -1,14 +1,16 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use modinverse::modinverse;
+use num::integer::{lcm, ExtendedGcd, Integer};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 const TIME_PASSED_SECONDS: i32 = 100;
 const ROOM_WIDTH: i32 = 101;
 const ROOM_HEIGHT: i32 = 103;
 
 #[derive(Clone, Copy, Debug)]
-struct Robot {
-    position: (i32, i32),
-    velocity: (i32, i32),
+pub struct Robot {
+    pub position: (i32, i32),
+    pub velocity: (i32, i32),
 }
 
 #[aoc_generator(day14)]
@@ -76,30 +78,24 @@ fn move_robots(robots: &[Robot], room_width: i32, room_height: i32, time: i32) -
         .collect()
 }
 
-fn variances(robots: &[Robot]) -> (f32, f32) {
-    let count = robots.len() as f32;
-    let mean_x = robots.iter().map(|robot| robot.position.0).sum::<i32>() as f32 / count;
-    let mean_y = robots.iter().map(|robot| robot.position.1).sum::<i32>() as f32 / count;
+fn variance(values: impl Iterator<Item = i32> + Clone) -> f32 {
+    let count = values.clone().count() as f32;
+    let mean = values.clone().sum::<i32>() as f32 / count;
 
-    let variance_x = robots
-        .iter()
-        .map(|robot| {
-            let diff = mean_x - robot.position.0 as f32;
-            diff * diff
-        })
-        .sum::<f32>()
-        / count;
-
-    let variance_y = robots
-        .iter()
-        .map(|robot| {
-            let diff = mean_y - robot.position.1 as f32;
+    values
+        .map(|value| {
+            let diff = mean - value as f32;
             diff * diff
         })
         .sum::<f32>()
-        / count;
+        / count
+}
 
-    (variance_x, variance_y)
+fn variances(positions: &[(i32, i32)]) -> (f32, f32) {
+    (
+        variance(positions.iter().map(|position| position.0)),
+        variance(positions.iter().map(|position| position.1)),
+    )
 }
 
 #[aoc(day14, part2)]
@@ -111,7 +107,8 @@ fn part2(robots: &[Robot]) -> i32 {
 
     for time_passed in 1..=usize::max(ROOM_WIDTH as usize, ROOM_HEIGHT as usize) {
         let robots = move_robots(robots, ROOM_WIDTH, ROOM_HEIGHT, time_passed as i32);
-        let (variance_x, variance_y) = variances(&robots);
+        let positions: Vec<(i32, i32)> = robots.iter().map(|robot| robot.position).collect();
+        let (variance_x, variance_y) = variances(&positions);
 
         match min_variance_x {
             Some(min_variance_x) if min_variance_x < variance_x => {}
@@ -138,6 +135,193 @@ fn part2(robots: &[Robot]) -> i32 {
     (time % max_time + max_time) % max_time
 }
 
+pub trait Detector {
+    fn score(&self, positions: &[(i32, i32)]) -> f64;
+}
+
+pub struct Variance;
+
+impl Detector for Variance {
+    fn score(&self, positions: &[(i32, i32)]) -> f64 {
+        let (variance_x, variance_y) = variances(positions);
+
+        -(variance_x as f64 + variance_y as f64)
+    }
+}
+
+pub struct Entropy {
+    block_size: i32,
+}
+
+impl Entropy {
+    pub fn new(block_size: i32) -> Option<Self> {
+        (block_size > 0).then_some(Entropy { block_size })
+    }
+}
+
+impl Detector for Entropy {
+    fn score(&self, positions: &[(i32, i32)]) -> f64 {
+        let counts = positions.iter().fold(HashMap::new(), |mut counts, (x, y)| {
+            *counts
+                .entry((x / self.block_size, y / self.block_size))
+                .or_insert(0usize) += 1;
+            counts
+        });
+
+        let total = positions.len() as f64;
+
+        counts
+            .values()
+            .map(|count| {
+                let probability = *count as f64 / total;
+                probability * probability.log2()
+            })
+            .sum()
+    }
+}
+
+pub struct LargestComponent;
+
+impl Detector for LargestComponent {
+    fn score(&self, positions: &[(i32, i32)]) -> f64 {
+        let occupied: HashSet<(i32, i32)> = positions.iter().copied().collect();
+        let mut visited = HashSet::new();
+        let mut largest = 0;
+
+        for start in occupied.iter() {
+            if !visited.insert(*start) {
+                continue;
+            }
+
+            let mut size = 0;
+            let mut queue = VecDeque::from([*start]);
+
+            while let Some((x, y)) = queue.pop_front() {
+                size += 1;
+
+                for next_position in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                    if occupied.contains(&next_position) && visited.insert(next_position) {
+                        queue.push_back(next_position);
+                    }
+                }
+            }
+
+            largest = largest.max(size);
+        }
+
+        largest as f64
+    }
+}
+
+pub struct UniquePositions;
+
+impl Detector for UniquePositions {
+    fn score(&self, positions: &[(i32, i32)]) -> f64 {
+        positions.iter().collect::<HashSet<_>>().len() as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub time: i32,
+    pub score: f64,
+}
+
+fn combine_congruences(
+    (remainder_a, modulus_a): (i32, i32),
+    (remainder_b, modulus_b): (i32, i32),
+) -> Option<i32> {
+    let ExtendedGcd { gcd, x, .. } = (modulus_a as i64).extended_gcd(&(modulus_b as i64));
+    let difference = (remainder_b - remainder_a) as i64;
+
+    if difference % gcd != 0 {
+        return None;
+    }
+
+    let combined_modulus = modulus_a as i64 / gcd * modulus_b as i64;
+    let steps = (difference / gcd * x).rem_euclid(modulus_b as i64 / gcd);
+
+    Some((remainder_a as i64 + modulus_a as i64 * steps).rem_euclid(combined_modulus) as i32)
+}
+
+pub struct Swarm {
+    robots: Vec<Robot>,
+    room_width: i32,
+    room_height: i32,
+}
+
+impl Swarm {
+    pub fn new(robots: &[Robot], room_width: i32, room_height: i32) -> Option<Self> {
+        (room_width > 0 && room_height > 0).then(|| Swarm {
+            robots: robots.to_vec(),
+            room_width,
+            room_height,
+        })
+    }
+
+    pub fn period(&self) -> i32 {
+        lcm(self.room_width, self.room_height)
+    }
+
+    pub fn positions_at(&self, time: i32) -> Vec<(i32, i32)> {
+        move_robots(
+            &self.robots,
+            self.room_width,
+            self.room_height,
+            time % self.period(),
+        )
+        .iter()
+        .map(|robot| robot.position)
+        .collect()
+    }
+
+    pub fn search(&self, detector: &impl Detector, top: usize) -> Vec<Candidate> {
+        let mut candidates: Vec<Candidate> = (0..self.period())
+            .map(|time| Candidate {
+                time,
+                score: detector.score(&self.positions_at(time)),
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.time.cmp(&b.time)));
+        candidates.truncate(top);
+
+        candidates
+    }
+
+    pub fn aligned_time(&self) -> Option<i32> {
+        let min_variance_time = |period: i32, axis: fn(&(i32, i32)) -> i32| {
+            (0..period)
+                .map(|time| (time, variance(self.positions_at(time).iter().map(axis))))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(time, _)| time)
+                .unwrap_or(0)
+        };
+
+        let time_x = min_variance_time(self.room_width, |position| position.0);
+        let time_y = min_variance_time(self.room_height, |position| position.1);
+
+        combine_congruences((time_x, self.room_width), (time_y, self.room_height)).or_else(|| {
+            self.search(&Variance, 1)
+                .first()
+                .map(|candidate| candidate.time)
+        })
+    }
+}
+
+#[aoc(day14, part2, swarm)]
+fn part2_swarm(robots: &[Robot]) -> Option<i32> {
+    Swarm::new(robots, ROOM_WIDTH, ROOM_HEIGHT)?.aligned_time()
+}
+
+#[aoc(day14, part2, unique)]
+fn part2_unique(robots: &[Robot]) -> Option<i32> {
+    Swarm::new(robots, ROOM_WIDTH, ROOM_HEIGHT)?
+        .search(&UniquePositions, 1)
+        .first()
+        .map(|candidate| candidate.time)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,4 +346,115 @@ p=9,5 v=-3,-3";
             12
         );
     }
+
+    #[test]
+    fn swarm_positions() {
+        let swarm = Swarm::new(&parse_input("p=2,4 v=2,-3"), 11, 7).unwrap();
+
+        assert_eq!(swarm.period(), 77);
+        assert_eq!(swarm.positions_at(5), vec![(1, 3)]);
+        assert_eq!(swarm.positions_at(82), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn non_coprime_rooms() {
+        assert_eq!(combine_congruences((3, 4), (5, 6)), Some(11));
+        assert_eq!(combine_congruences((1, 4), (2, 6)), None);
+
+        let swarm = Swarm::new(
+            &parse_input(
+                "p=4,6 v=5,-3
+p=0,7 v=1,5
+p=1,4 v=1,-1
+p=11,5 v=11,-1
+p=8,4 v=-5,-1
+p=6,3 v=5,1",
+            ),
+            12,
+            8,
+        )
+        .unwrap();
+
+        assert_eq!(swarm.period(), 24);
+        assert_eq!(swarm.aligned_time(), Some(17));
+        assert_eq!(
+            swarm.positions_at(17),
+            vec![(5, 3), (5, 4), (6, 3), (6, 4), (7, 3), (7, 4)]
+        );
+        assert_eq!(swarm.positions_at(41), swarm.positions_at(17));
+
+        assert!(Swarm::new(&parse_input(TEST_INPUT), 0, 7).is_none());
+        assert!(Swarm::new(&parse_input(TEST_INPUT), 11, -7).is_none());
+    }
+
+    #[test]
+    fn detector_scores() {
+        let square = [(0, 0), (2, 0), (0, 2), (2, 2)];
+
+        assert_eq!(Variance.score(&square), -2.0);
+        assert_eq!(Entropy::new(1).unwrap().score(&square), -2.0);
+        assert_eq!(Entropy::new(3).unwrap().score(&square), 0.0);
+        assert_eq!(LargestComponent.score(&square), 1.0);
+        assert_eq!(UniquePositions.score(&square), 4.0);
+
+        let frame = [
+            (0, 0),
+            (1, 0),
+            (1, 1),
+            (1, 1),
+            (4, 4),
+            (4, 5),
+            (5, 4),
+            (5, 5),
+        ];
+
+        assert_eq!(Entropy::new(2).unwrap().score(&frame), -1.0);
+        assert_eq!(LargestComponent.score(&frame), 4.0);
+        assert_eq!(UniquePositions.score(&frame), 7.0);
+
+        assert!(Entropy::new(0).is_none());
+        assert!(Entropy::new(-2).is_none());
+    }
+
+    #[test]
+    fn hidden_block() {
+        let swarm = Swarm::new(
+            &parse_input(
+                "p=1,1 v=-1,4
+p=9,1 v=-2,1
+p=2,3 v=3,-3
+p=4,3 v=-4,3
+p=2,5 v=-1,-1
+p=3,3 v=3,4
+p=7,3 v=4,3
+p=1,0 v=2,-2
+p=3,1 v=-1,-2
+p=8,5 v=4,2
+p=6,2 v=-4,-3
+p=1,5 v=-2,-4",
+            ),
+            11,
+            7,
+        )
+        .unwrap();
+        let mut block = swarm.positions_at(30);
+        block.sort();
+
+        assert_eq!(
+            block,
+            (4..8)
+                .flat_map(|x| (2..5).map(move |y| (x, y)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(swarm.search(&Variance, 1)[0].time, 30);
+        assert_eq!(swarm.search(&Entropy::new(2).unwrap(), 1)[0].time, 30);
+        assert_eq!(
+            swarm.search(&LargestComponent, 1),
+            vec![Candidate {
+                time: 30,
+                score: 12.0
+            }]
+        );
+        assert_eq!(swarm.aligned_time(), Some(30));
+    }
 }
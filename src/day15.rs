@@ -1,5 +1,7 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
 
 struct WarehouseSetup {
     walls: HashSet<(i32, i32)>,
@@ -53,129 +55,268 @@ fn parse_input(input: &str) -> WarehouseSetup {
     }
 }
 
-#[aoc(day15, part1)]
-fn part1(warehouse_setup: &WarehouseSetup) -> i32 {
-    let walls = &warehouse_setup.walls;
-    let mut boxes = warehouse_setup.boxes.clone();
-    let mut robot = warehouse_setup.robot;
-
-    for robot_move in warehouse_setup.moves.iter() {
-        let mut boxes_to_move = HashSet::new();
-
-        let mut position = (robot.0 + robot_move.0, robot.1 + robot_move.1);
-
-        loop {
-            if boxes.contains(&position) {
-                boxes_to_move.insert(position);
-                position.0 += robot_move.0;
-                position.1 += robot_move.1;
-            } else if walls.contains(&position) {
-                boxes_to_move.clear();
-                break;
-            } else {
-                robot.0 += robot_move.0;
-                robot.1 += robot_move.1;
-                break;
-            }
-        }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WarehouseBox {
+    pub position: (i32, i32),
+    pub width: i32,
+    pub height: i32,
+}
 
-        for moved_box in boxes_to_move.iter() {
-            boxes.remove(moved_box);
-        }
+impl WarehouseBox {
+    fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..self.height).flat_map(move |dy| {
+            (0..self.width).map(move |dx| (self.position.0 + dx, self.position.1 + dy))
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidState {
+    EmptyBox { index: usize },
+    Overlap { position: (i32, i32) },
+    UnknownRobot { index: usize },
+    InvalidScale { factor: i32 },
+    InvalidDirection { direction: (i32, i32) },
+}
 
-        for moved_box in boxes_to_move.iter() {
-            boxes.insert((moved_box.0 + robot_move.0, moved_box.1 + robot_move.1));
+impl fmt::Display for InvalidState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidState::EmptyBox { index } => write!(f, "box {} has no cells", index),
+            InvalidState::Overlap { position } => write!(
+                f,
+                "cell ({}, {}) is occupied more than once",
+                position.0, position.1
+            ),
+            InvalidState::UnknownRobot { index } => write!(f, "there is no robot {}", index),
+            InvalidState::InvalidScale { factor } => {
+                write!(f, "scale factor {} is not positive", factor)
+            }
+            InvalidState::InvalidDirection { direction } => write!(
+                f,
+                "direction ({}, {}) is not a unit step",
+                direction.0, direction.1
+            ),
         }
     }
+}
+
+impl Error for InvalidState {}
 
-    boxes.iter().map(|(x, y)| 100 * y + x).sum::<i32>()
+#[derive(Clone, Debug)]
+struct Step {
+    robot: usize,
+    direction: (i32, i32),
+    moved_boxes: Vec<usize>,
 }
 
-#[aoc(day15, part2)]
-fn part2(warehouse_setup: &WarehouseSetup) -> i32 {
-    let walls: HashSet<(i32, i32)> = warehouse_setup
-        .walls
-        .iter()
-        .flat_map(|(x, y)| [(2 * x, *y), (2 * x + 1, *y)])
-        .collect();
-    let mut boxes: HashSet<(i32, i32)> = warehouse_setup
-        .boxes
-        .iter()
-        .map(|(x, y)| (2 * x, *y))
-        .collect();
-    let mut robot = (warehouse_setup.robot.0 * 2, warehouse_setup.robot.1);
-
-    for robot_move in warehouse_setup.moves.iter() {
-        let mut boxes_to_move = HashSet::new();
-
-        if robot_move.0 == 0 {
-            let mut front = HashSet::from([(robot.0, robot.1 + robot_move.1)]);
-
-            loop {
-                if walls.intersection(&front).count() > 0 {
-                    boxes_to_move.clear();
-                    break;
+#[derive(Clone, Debug)]
+pub struct Warehouse {
+    walls: HashSet<(i32, i32)>,
+    boxes: Vec<WarehouseBox>,
+    robots: Vec<(i32, i32)>,
+    occupied_by_boxes: HashMap<(i32, i32), usize>,
+    history: Vec<Step>,
+}
+
+impl Warehouse {
+    pub fn new(
+        walls: HashSet<(i32, i32)>,
+        boxes: Vec<WarehouseBox>,
+        robots: Vec<(i32, i32)>,
+    ) -> Result<Self, InvalidState> {
+        let mut occupied_by_boxes = HashMap::new();
+
+        for (index, warehouse_box) in boxes.iter().enumerate() {
+            if warehouse_box.width < 1 || warehouse_box.height < 1 {
+                return Err(InvalidState::EmptyBox { index });
+            }
+
+            for position in warehouse_box.cells() {
+                if walls.contains(&position) || occupied_by_boxes.insert(position, index).is_some()
+                {
+                    return Err(InvalidState::Overlap { position });
                 }
+            }
+        }
 
-                let mut touched_boxes = HashSet::with_capacity(front.len());
+        let mut occupied_by_robots = HashSet::new();
 
-                for front_piece in front.iter() {
-                    if boxes.contains(&(front_piece.0, front_piece.1)) {
-                        touched_boxes.insert((front_piece.0, front_piece.1));
-                    }
+        for robot in robots.iter() {
+            if walls.contains(robot)
+                || occupied_by_boxes.contains_key(robot)
+                || !occupied_by_robots.insert(*robot)
+            {
+                return Err(InvalidState::Overlap { position: *robot });
+            }
+        }
 
-                    if boxes.contains(&(front_piece.0 - 1, front_piece.1)) {
-                        touched_boxes.insert((front_piece.0 - 1, front_piece.1));
-                    }
-                }
+        Ok(Warehouse {
+            walls,
+            boxes,
+            robots,
+            occupied_by_boxes,
+            history: Vec::new(),
+        })
+    }
 
-                if touched_boxes.is_empty() {
-                    robot.1 += robot_move.1;
-                    break;
-                }
+    pub fn boxes(&self) -> &[WarehouseBox] {
+        &self.boxes
+    }
 
-                let mut new_front = HashSet::with_capacity(touched_boxes.len() * 2);
+    pub fn robots(&self) -> &[(i32, i32)] {
+        &self.robots
+    }
 
-                for touched_box in touched_boxes.iter() {
-                    boxes_to_move.insert(*touched_box);
-                    new_front.insert((touched_box.0, touched_box.1 + robot_move.1));
-                    new_front.insert((touched_box.0 + 1, touched_box.1 + robot_move.1));
-                }
+    pub fn scaled(&self, factor_x: i32) -> Result<Self, InvalidState> {
+        if factor_x < 1 {
+            return Err(InvalidState::InvalidScale { factor: factor_x });
+        }
+
+        let walls = self
+            .walls
+            .iter()
+            .flat_map(|(x, y)| (0..factor_x).map(move |dx| (factor_x * x + dx, *y)))
+            .collect();
+        let boxes = self
+            .boxes
+            .iter()
+            .map(|warehouse_box| WarehouseBox {
+                position: (
+                    factor_x * warehouse_box.position.0,
+                    warehouse_box.position.1,
+                ),
+                width: factor_x * warehouse_box.width,
+                height: warehouse_box.height,
+            })
+            .collect();
+        let robots = self
+            .robots
+            .iter()
+            .map(|(x, y)| (factor_x * x, *y))
+            .collect();
+
+        Warehouse::new(walls, boxes, robots)
+    }
 
-                front = new_front;
+    fn move_boxes(&mut self, moved_boxes: &[usize], direction: (i32, i32)) {
+        for index in moved_boxes {
+            for position in self.boxes[*index].cells() {
+                self.occupied_by_boxes.remove(&position);
             }
-        } else {
-            let mut front = (robot.0 + robot_move.0, robot.1);
+        }
 
-            loop {
-                if walls.contains(&front) {
-                    boxes_to_move.clear();
-                    break;
-                }
+        for index in moved_boxes {
+            let warehouse_box = &mut self.boxes[*index];
+            warehouse_box.position.0 += direction.0;
+            warehouse_box.position.1 += direction.1;
 
-                if boxes.contains(&(front.0, front.1)) {
-                    boxes_to_move.insert((front.0, front.1));
-                    front = (front.0 + 2 * robot_move.0, front.1);
-                } else if boxes.contains(&(front.0 - 1, front.1)) {
-                    boxes_to_move.insert((front.0 - 1, front.1));
-                    front = (front.0 + 2 * robot_move.0, front.1);
-                } else {
-                    robot.0 += robot_move.0;
-                    break;
-                }
+            for position in warehouse_box.cells() {
+                self.occupied_by_boxes.insert(position, *index);
             }
         }
+    }
 
-        for moved_box in boxes_to_move.iter() {
-            boxes.remove(moved_box);
+    pub fn push(&mut self, robot: usize, direction: (i32, i32)) -> Result<bool, InvalidState> {
+        let Some(&(robot_x, robot_y)) = self.robots.get(robot) else {
+            return Err(InvalidState::UnknownRobot { index: robot });
+        };
+
+        if direction.0.abs() + direction.1.abs() != 1 {
+            return Err(InvalidState::InvalidDirection { direction });
         }
 
-        for moved_box in boxes_to_move.iter() {
-            boxes.insert((moved_box.0 + robot_move.0, moved_box.1 + robot_move.1));
+        let mut moved_boxes = Vec::new();
+        let mut included = HashSet::new();
+        let mut front = VecDeque::from([(robot_x + direction.0, robot_y + direction.1)]);
+
+        while let Some(position) = front.pop_front() {
+            if self.walls.contains(&position) || self.robots.contains(&position) {
+                return Ok(false);
+            }
+
+            if let Some(&index) = self.occupied_by_boxes.get(&position) {
+                if included.insert(index) {
+                    moved_boxes.push(index);
+                    front.extend(
+                        self.boxes[index]
+                            .cells()
+                            .map(|(x, y)| (x + direction.0, y + direction.1)),
+                    );
+                }
+            }
         }
+
+        self.move_boxes(&moved_boxes, direction);
+        self.robots[robot] = (robot_x + direction.0, robot_y + direction.1);
+        self.history.push(Step {
+            robot,
+            direction,
+            moved_boxes,
+        });
+
+        Ok(true)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(step) = self.history.pop() else {
+            return false;
+        };
+
+        let direction = (-step.direction.0, -step.direction.1);
+
+        self.move_boxes(&step.moved_boxes, direction);
+
+        let robot = &mut self.robots[step.robot];
+        robot.0 += direction.0;
+        robot.1 += direction.1;
+
+        true
+    }
+
+    pub fn gps_sum(&self) -> i32 {
+        self.boxes
+            .iter()
+            .map(|warehouse_box| 100 * warehouse_box.position.1 + warehouse_box.position.0)
+            .sum()
     }
+}
 
-    boxes.iter().map(|(x, y)| 100 * y + x).sum::<i32>()
+impl WarehouseSetup {
+    fn warehouse(&self) -> Warehouse {
+        let boxes = self
+            .boxes
+            .iter()
+            .map(|position| WarehouseBox {
+                position: *position,
+                width: 1,
+                height: 1,
+            })
+            .collect();
+
+        Warehouse::new(self.walls.clone(), boxes, vec![self.robot]).unwrap()
+    }
+}
+
+fn simulate(mut warehouse: Warehouse, moves: &[(i32, i32)]) -> i32 {
+    for robot_move in moves {
+        warehouse.push(0, *robot_move).unwrap();
+    }
+
+    warehouse.gps_sum()
+}
+
+#[aoc(day15, part1)]
+fn part1(warehouse_setup: &WarehouseSetup) -> i32 {
+    simulate(warehouse_setup.warehouse(), &warehouse_setup.moves)
+}
+
+#[aoc(day15, part2)]
+fn part2(warehouse_setup: &WarehouseSetup) -> i32 {
+    simulate(
+        warehouse_setup.warehouse().scaled(2).unwrap(),
+        &warehouse_setup.moves,
+    )
 }
 
 #[cfg(test)]
@@ -229,4 +370,62 @@ v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^";
     fn part2_example() {
         assert_eq!(part2(&parse_input(TEST_INPUT_LARGE)), 9_021);
     }
+
+    #[test]
+    fn undo_restores_state() {
+        let warehouse_setup = parse_input(TEST_INPUT_SMALL);
+        let mut warehouse = warehouse_setup.warehouse().scaled(2).unwrap();
+        let initial_boxes = warehouse.boxes().to_vec();
+
+        for robot_move in warehouse_setup.moves.iter() {
+            warehouse.push(0, *robot_move).unwrap();
+        }
+
+        while warehouse.undo() {}
+
+        assert_eq!(warehouse.boxes(), initial_boxes);
+        assert_eq!(warehouse.robots(), [(4, 2)]);
+    }
+
+    #[test]
+    fn large_boxes_and_robots() {
+        let walls: HashSet<(i32, i32)> = (0..8)
+            .flat_map(|i| [(i, 0), (i, 6), (0, i.min(6)), (7, i.min(6))])
+            .collect();
+        let big_box = WarehouseBox {
+            position: (2, 2),
+            width: 3,
+            height: 2,
+        };
+
+        assert_eq!(
+            Warehouse::new(walls.clone(), vec![big_box], vec![(3, 3)]).err(),
+            Some(InvalidState::Overlap { position: (3, 3) })
+        );
+
+        let mut warehouse = Warehouse::new(walls, vec![big_box], vec![(4, 4), (3, 1)]).unwrap();
+
+        assert_eq!(warehouse.push(0, (0, -1)), Ok(false));
+        assert_eq!(warehouse.push(1, (1, 0)), Ok(true));
+        assert_eq!(warehouse.push(1, (1, 0)), Ok(true));
+        assert_eq!(warehouse.push(0, (0, -1)), Ok(true));
+        assert_eq!(warehouse.boxes()[0].position, (2, 1));
+        assert_eq!(
+            warehouse.push(2, (0, 1)),
+            Err(InvalidState::UnknownRobot { index: 2 })
+        );
+        assert_eq!(
+            warehouse.push(0, (0, 2)),
+            Err(InvalidState::InvalidDirection { direction: (0, 2) })
+        );
+        assert_eq!(
+            warehouse.push(0, (1, 1)),
+            Err(InvalidState::InvalidDirection { direction: (1, 1) })
+        );
+        assert_eq!(
+            warehouse.scaled(0).err(),
+            Some(InvalidState::InvalidScale { factor: 0 })
+        );
+        assert_eq!(warehouse.scaled(3).unwrap().boxes()[0].width, 9);
+    }
 }
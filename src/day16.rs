@@ -1,6 +1,8 @@
+use crate::search::{all_optimal_paths, astar, NonPositiveStep, SearchProblem};
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
 
 const TURN_COST: i32 = 1_000;
 const MOVE_COST: i32 = 1;
@@ -8,273 +10,296 @@ const MOVE_COST: i32 = 1;
 type Position = (i32, i32);
 type Direction = (i32, i32);
 
+const DIRECTIONS: [Direction; 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
 #[derive(Debug)]
-struct RaceSetup {
+pub struct RaceSetup {
     obstacles: HashSet<Position>,
+    tile_weights: HashMap<Position, i32>,
     start: Position,
     start_direction: Direction,
     end: Position,
 }
 
-#[aoc_generator(day16)]
-fn parse_input(input: &str) -> RaceSetup {
-    let mut start = (0, 0);
-    let start_direction = (1, 0);
-    let mut end = (0, 0);
-
-    let mut obstacles: HashSet<(i32, i32)> = HashSet::new();
-
-    input.lines().enumerate().for_each(|(y, row)| {
-        row.chars().enumerate().for_each(|(x, tile)| match tile {
-            '#' => {
-                obstacles.insert((x as i32, y as i32));
-            }
-            'S' => {
-                start = (x as i32, y as i32);
-            }
-            'E' => {
-                end = (x as i32, y as i32);
-            }
-            _ => (),
-        })
-    });
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostError {
+    NonPositiveCost(i32),
+    NonPositiveWeight { position: Position, weight: i32 },
+}
 
-    RaceSetup {
-        obstacles,
-        start,
-        start_direction,
-        end,
+impl fmt::Display for CostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CostError::NonPositiveCost(cost) => write!(f, "cost {} is not positive", cost),
+            CostError::NonPositiveWeight { position, weight } => write!(
+                f,
+                "tile weight {} at ({}, {}) is not positive",
+                weight, position.0, position.1
+            ),
+        }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct State {
-    position: Position,
-    direction: Direction,
-    end: Position,
-    score: i32,
+impl Error for CostError {}
+
+fn positive(cost: i32) -> Result<i32, CostError> {
+    if cost > 0 {
+        Ok(cost)
+    } else {
+        Err(CostError::NonPositiveCost(cost))
+    }
 }
 
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        let self_priority = self.score
-            + (self.position.0 - self.end.0).abs()
-            + (self.position.1 - self.end.1).abs()
-            + {
-                if self.position.0 != self.end.0 && self.position.1 != self.end.1 {
-                    TURN_COST
-                } else {
-                    0
+impl RaceSetup {
+    pub fn parse(input: &str) -> Result<Self, CostError> {
+        let mut start = (0, 0);
+        let start_direction = (1, 0);
+        let mut end = (0, 0);
+
+        let mut obstacles: HashSet<Position> = HashSet::new();
+        let mut tile_weights: HashMap<Position, i32> = HashMap::new();
+
+        for (y, row) in input.lines().enumerate() {
+            for (x, tile) in row.chars().enumerate() {
+                let position = (x as i32, y as i32);
+
+                match tile {
+                    '#' => {
+                        obstacles.insert(position);
+                    }
+                    'S' => start = position,
+                    'E' => end = position,
+                    '0'..='9' => {
+                        let weight = tile as i32 - '0' as i32;
+
+                        if weight <= 0 {
+                            return Err(CostError::NonPositiveWeight { position, weight });
+                        }
+
+                        tile_weights.insert(position, weight);
+                    }
+                    _ => (),
                 }
-            };
-
-        let other_priority = other.score
-            + (other.position.0 - other.end.0).abs()
-            + (other.position.1 - other.end.1).abs()
-            + {
-                if other.position.0 != other.end.0 && other.position.1 != other.end.1 {
-                    TURN_COST
-                } else {
-                    0
-                }
-            };
+            }
+        }
 
-        other_priority.cmp(&self_priority)
+        Ok(RaceSetup {
+            obstacles,
+            tile_weights,
+            start,
+            start_direction,
+            end,
+        })
     }
 }
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+#[aoc_generator(day16)]
+fn parse_input(input: &str) -> RaceSetup {
+    RaceSetup::parse(input).unwrap()
 }
 
-#[aoc(day16, part1)]
-fn part1(race_setup: &RaceSetup) -> Option<i32> {
-    let mut min_scores: HashMap<(Position, Direction), i32> = HashMap::new();
-
-    let mut frontier = BinaryHeap::new();
-
-    frontier.push(State {
-        position: race_setup.start,
-        direction: race_setup.start_direction,
-        end: race_setup.end,
-        score: 0,
-    });
-
-    while let Some(State {
-        position,
-        direction,
-        end,
-        score,
-    }) = frontier.pop()
-    {
-        if position == end {
-            return Some(score);
-        }
-
-        let min_score = min_scores.entry((position, direction)).or_insert(score);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovementModel {
+    move_cost: i32,
+    turn_cost: i32,
+    u_turn_cost: Option<i32>,
+    diagonal_costs: Option<(i32, i32)>,
+}
 
-        if score > *min_score {
-            continue;
+impl Default for MovementModel {
+    fn default() -> Self {
+        MovementModel {
+            move_cost: MOVE_COST,
+            turn_cost: TURN_COST,
+            u_turn_cost: None,
+            diagonal_costs: None,
         }
+    }
+}
 
-        for new_state in [
-            State {
-                position: (position.0 + direction.0, position.1 + direction.1),
-                direction,
-                end,
-                score: score + MOVE_COST,
-            },
-            State {
-                position,
-                direction: (direction.1, direction.0),
-                end,
-                score: score + TURN_COST,
-            },
-            State {
-                position,
-                direction: (-direction.1, -direction.0),
-                end,
-                score: score + TURN_COST,
-            },
-        ] {
-            if race_setup.obstacles.contains(&new_state.position) {
-                continue;
-            }
-
-            let min_score = min_scores
-                .entry((new_state.position, new_state.direction))
-                .or_insert(new_state.score + 1);
+impl MovementModel {
+    pub fn new(move_cost: i32, turn_cost: i32) -> Result<Self, CostError> {
+        Ok(MovementModel {
+            move_cost: positive(move_cost)?,
+            turn_cost: positive(turn_cost)?,
+            u_turn_cost: None,
+            diagonal_costs: None,
+        })
+    }
 
-            if new_state.score < *min_score {
-                *min_score = new_state.score;
-                frontier.push(new_state);
-            }
-        }
+    pub fn with_u_turn_cost(self, u_turn_cost: i32) -> Result<Self, CostError> {
+        Ok(MovementModel {
+            u_turn_cost: Some(positive(u_turn_cost)?),
+            ..self
+        })
     }
 
-    None
+    pub fn with_diagonal_moves(
+        self,
+        diagonal_move_cost: i32,
+        diagonal_turn_cost: i32,
+    ) -> Result<Self, CostError> {
+        Ok(MovementModel {
+            diagonal_costs: Some((positive(diagonal_move_cost)?, positive(diagonal_turn_cost)?)),
+            ..self
+        })
+    }
 }
 
-#[derive(Clone, Eq, PartialEq)]
-struct ExtendedState {
-    position: Position,
-    direction: Direction,
-    end: Position,
-    score: i32,
-    path: Vec<Position>,
+struct Race<'a> {
+    race_setup: &'a RaceSetup,
+    model: MovementModel,
+    min_weight: i32,
 }
 
-impl Ord for ExtendedState {
-    fn cmp(&self, other: &Self) -> Ordering {
-        State {
-            position: self.position,
-            direction: self.direction,
-            end: self.end,
-            score: self.score,
+impl<'a> Race<'a> {
+    fn new(race_setup: &'a RaceSetup, model: MovementModel) -> Self {
+        Race {
+            race_setup,
+            model,
+            min_weight: race_setup
+                .tile_weights
+                .values()
+                .fold(1, |min, w| min.min(*w)),
         }
-        .cmp(&State {
-            position: other.position,
-            direction: other.direction,
-            end: other.end,
-            score: other.score,
-        })
     }
-}
 
-impl PartialOrd for ExtendedState {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn weight(&self, position: &Position) -> i32 {
+        *self.race_setup.tile_weights.get(position).unwrap_or(&1)
     }
 }
 
-#[aoc(day16, part2)]
-fn part2(race_setup: &RaceSetup) -> usize {
-    let mut min_scores: HashMap<(Position, Direction), i32> = HashMap::new();
-    let mut final_score: Option<i32> = None;
-    let mut best_seats: HashSet<Position> = HashSet::new();
-
-    let mut frontier = BinaryHeap::new();
-
-    frontier.push(ExtendedState {
-        position: race_setup.start,
-        direction: race_setup.start_direction,
-        end: race_setup.end,
-        score: 0,
-        path: vec![race_setup.start],
-    });
-
-    while let Some(ExtendedState {
-        position,
-        direction,
-        end,
-        score,
-        path,
-    }) = frontier.pop()
-    {
-        match final_score {
-            Some(old_score) if old_score < score => {
-                continue;
-            }
-            _ => {
-                if position == end {
-                    final_score = Some(score);
-                    best_seats.extend(path.into_iter());
-                    continue;
-                }
-            }
-        };
+impl SearchProblem for Race<'_> {
+    type State = (Position, Direction);
+    type Cost = i32;
+
+    fn starts(&self) -> Vec<Self::State> {
+        vec![(self.race_setup.start, self.race_setup.start_direction)]
+    }
+
+    fn successors(&self, &(position, direction): &Self::State) -> Vec<(Self::State, i32)> {
+        let obstacles = &self.race_setup.obstacles;
+        let index = DIRECTIONS.iter().position(|d| *d == direction).unwrap();
+        let rotated = |steps: usize| DIRECTIONS[(index + steps) % DIRECTIONS.len()];
 
-        let min_score = min_scores.entry((position, direction)).or_insert(score);
+        let mut successors = vec![
+            ((position, rotated(2)), self.model.turn_cost),
+            ((position, rotated(6)), self.model.turn_cost),
+        ];
 
-        if score > *min_score {
-            continue;
+        if let Some((_, diagonal_turn_cost)) = self.model.diagonal_costs {
+            successors.push(((position, rotated(1)), diagonal_turn_cost));
+            successors.push(((position, rotated(7)), diagonal_turn_cost));
         }
 
-        for new_state in [
-            ExtendedState {
-                position: (position.0 + direction.0, position.1 + direction.1),
-                direction,
-                end,
-                score: score + MOVE_COST,
-                path: {
-                    let mut new_path = path.clone();
-                    new_path.push((position.0 + direction.0, position.1 + direction.1));
-                    new_path
-                },
-            },
-            ExtendedState {
-                position,
-                direction: (direction.1, direction.0),
-                end,
-                score: score + TURN_COST,
-                path: path.clone(),
-            },
-            ExtendedState {
-                position,
-                direction: (-direction.1, -direction.0),
-                end,
-                score: score + TURN_COST,
-                path: path.clone(),
-            },
-        ] {
-            if race_setup.obstacles.contains(&new_state.position) {
-                continue;
+        if let Some(u_turn_cost) = self.model.u_turn_cost {
+            successors.push(((position, rotated(4)), u_turn_cost));
+        }
+
+        let next_position = (position.0 + direction.0, position.1 + direction.1);
+
+        let move_cost = if direction.0 != 0 && direction.1 != 0 {
+            if obstacles.contains(&(next_position.0, position.1))
+                || obstacles.contains(&(position.0, next_position.1))
+            {
+                None
+            } else {
+                self.model
+                    .diagonal_costs
+                    .map(|(diagonal_move_cost, _)| diagonal_move_cost)
+            }
+        } else {
+            Some(self.model.move_cost)
+        };
+
+        if let Some(move_cost) = move_cost {
+            if !obstacles.contains(&next_position) {
+                successors.push((
+                    (next_position, direction),
+                    move_cost * self.weight(&next_position),
+                ));
             }
+        }
+
+        successors
+    }
 
-            let min_score = min_scores
-                .entry((new_state.position, new_state.direction))
-                .or_insert(new_state.score + 1);
+    fn is_goal(&self, (position, _): &Self::State) -> bool {
+        *position == self.race_setup.end
+    }
+
+    fn heuristic(&self, (position, _): &Self::State) -> i32 {
+        let end = self.race_setup.end;
+        let (distance_x, distance_y) = ((position.0 - end.0).abs(), (position.1 - end.1).abs());
 
-            if new_state.score <= *min_score {
-                *min_score = new_state.score;
-                frontier.push(new_state);
+        match self.model.diagonal_costs {
+            Some((diagonal_move_cost, _)) => {
+                distance_x.max(distance_y)
+                    * self.min_weight
+                    * self.model.move_cost.min(diagonal_move_cost)
+            }
+            None => {
+                (distance_x + distance_y) * self.min_weight * self.model.move_cost + {
+                    if distance_x != 0 && distance_y != 0 {
+                        self.model.turn_cost
+                    } else {
+                        0
+                    }
+                }
             }
         }
     }
+}
 
-    best_seats.len()
+pub fn lowest_score(race_setup: &RaceSetup, model: MovementModel) -> Option<i32> {
+    astar(&Race::new(race_setup, model)).map(|path| path.cost)
+}
+
+#[aoc(day16, part1)]
+fn part1(race_setup: &RaceSetup) -> Option<i32> {
+    lowest_score(race_setup, MovementModel::default())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestPaths {
+    pub score: i32,
+    pub tiles: HashSet<Position>,
+    pub path_count: Option<u128>,
+}
+
+pub fn best_paths(
+    race_setup: &RaceSetup,
+    model: MovementModel,
+) -> Result<Option<BestPaths>, NonPositiveStep> {
+    let Some(optimal_paths) = all_optimal_paths(&Race::new(race_setup, model))? else {
+        return Ok(None);
+    };
+
+    Ok(Some(BestPaths {
+        score: optimal_paths.cost,
+        tiles: optimal_paths
+            .states()
+            .into_iter()
+            .map(|(position, _)| position)
+            .collect(),
+        path_count: optimal_paths.path_count(),
+    }))
+}
+
+#[aoc(day16, part2)]
+fn part2(race_setup: &RaceSetup) -> Option<usize> {
+    best_paths(race_setup, MovementModel::default())
+        .ok()?
+        .map(|best_paths| best_paths.tiles.len())
 }
 
 #[cfg(test)]
@@ -327,11 +352,120 @@ mod tests {
 
     #[test]
     fn part2_example_1() {
-        assert_eq!(part2(&parse_input(TEST_INPUT_1)), 45);
+        assert_eq!(part2(&parse_input(TEST_INPUT_1)), Some(45));
     }
 
     #[test]
     fn part2_example_2() {
-        assert_eq!(part2(&parse_input(TEST_INPUT_2)), 64);
+        assert_eq!(part2(&parse_input(TEST_INPUT_2)), Some(64));
+    }
+
+    #[test]
+    fn best_paths_summary() {
+        let example = best_paths(&parse_input(TEST_INPUT_1), MovementModel::default())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(example.score, 7_036);
+        assert_eq!(example.tiles.len(), 45);
+
+        let symmetric = best_paths(
+            &parse_input("#####\n#...#\n#S#E#\n#...#\n#####"),
+            MovementModel::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(symmetric.score, 3_004);
+        assert_eq!(symmetric.tiles.len(), 8);
+        assert_eq!(symmetric.path_count, Some(2));
+    }
+
+    #[test]
+    fn movement_variants() {
+        let open_room = parse_input("#####\n#..E#\n#...#\n#S..#\n#####");
+
+        assert_eq!(
+            lowest_score(&open_room, MovementModel::default()),
+            Some(1_004)
+        );
+        assert_eq!(
+            lowest_score(
+                &open_room,
+                MovementModel::default()
+                    .with_diagonal_moves(1, TURN_COST / 2)
+                    .unwrap()
+            ),
+            Some(502)
+        );
+        assert_eq!(
+            lowest_score(
+                &open_room,
+                MovementModel::new(MOVE_COST, 1)
+                    .unwrap()
+                    .with_diagonal_moves(1, TURN_COST)
+                    .unwrap()
+            ),
+            Some(5)
+        );
+
+        let dead_end = parse_input("#####\n#E.S#\n#####");
+
+        assert_eq!(
+            lowest_score(&dead_end, MovementModel::default()),
+            Some(2_002)
+        );
+        assert_eq!(
+            lowest_score(
+                &dead_end,
+                MovementModel::default().with_u_turn_cost(500).unwrap()
+            ),
+            Some(502)
+        );
+
+        let slow_corridor = parse_input("#####\n#...#\n#S9E#\n#####");
+
+        assert_eq!(
+            lowest_score(&slow_corridor, MovementModel::default()),
+            Some(10)
+        );
+        assert_eq!(
+            lowest_score(&slow_corridor, MovementModel::new(MOVE_COST, 1).unwrap()),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn positive_costs() {
+        assert_eq!(MovementModel::new(1, 0), Err(CostError::NonPositiveCost(0)));
+        assert_eq!(
+            MovementModel::default().with_diagonal_moves(1, -1),
+            Err(CostError::NonPositiveCost(-1))
+        );
+        assert_eq!(
+            MovementModel::default().with_u_turn_cost(0),
+            Err(CostError::NonPositiveCost(0))
+        );
+        assert_eq!(
+            RaceSetup::parse("#####\n#S0E#\n#####").map(|_| ()),
+            Err(CostError::NonPositiveWeight {
+                position: (2, 1),
+                weight: 0
+            })
+        );
+
+        let open_room = parse_input("#####\n#..E#\n#...#\n#S..#\n#####");
+        let cheap_turns = best_paths(
+            &open_room,
+            MovementModel::new(1, 1)
+                .unwrap()
+                .with_diagonal_moves(1, 1)
+                .unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(cheap_turns.score, 3);
+        assert_eq!(cheap_turns.path_count, Some(1));
     }
 }
@@ -1,71 +1,595 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
 
-#[derive(Debug, Clone)]
-struct Memory {
-    register_a: u64,
-    register_b: u64,
-    register_c: u64,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Memory {
+    pub register_a: u64,
+    pub register_b: u64,
+    pub register_c: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Adv,
+    Bxl,
+    Bst,
+    Jnz,
+    Bxc,
+    Out,
+    Bdv,
+    Cdv,
+}
+
+const OPCODES: [Opcode; 8] = [
+    Opcode::Adv,
+    Opcode::Bxl,
+    Opcode::Bst,
+    Opcode::Jnz,
+    Opcode::Bxc,
+    Opcode::Out,
+    Opcode::Bdv,
+    Opcode::Cdv,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OperandKind {
+    Literal,
+    Combo,
+    Ignored,
 }
 
-fn combo_operand(memory: &Memory, operand: u64) -> u64 {
-    match operand {
-        literal if literal <= 3 => literal,
-        4 => memory.register_a,
-        5 => memory.register_b,
-        6 => memory.register_c,
-        _ => unreachable!(),
+impl Opcode {
+    pub fn from_code(code: u64) -> Option<Self> {
+        OPCODES.get(usize::try_from(code).ok()?).copied()
+    }
+
+    pub fn code(self) -> u64 {
+        self as u64
     }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Adv => "adv",
+            Opcode::Bxl => "bxl",
+            Opcode::Bst => "bst",
+            Opcode::Jnz => "jnz",
+            Opcode::Bxc => "bxc",
+            Opcode::Out => "out",
+            Opcode::Bdv => "bdv",
+            Opcode::Cdv => "cdv",
+        }
+    }
+
+    fn operand_kind(self) -> OperandKind {
+        match self {
+            Opcode::Bxl | Opcode::Jnz => OperandKind::Literal,
+            Opcode::Bxc => OperandKind::Ignored,
+            _ => OperandKind::Combo,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub operand: u64,
 }
 
-fn run_program(memory: &mut Memory, program: &[u64]) -> Vec<u64> {
-    let mut instruction_pointer = 0;
-    let mut output = Vec::new();
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = self.opcode.mnemonic();
+
+        match (self.opcode.operand_kind(), self.operand) {
+            (OperandKind::Ignored, 0) => write!(f, "{}", mnemonic),
+            (OperandKind::Combo, 4) => write!(f, "{} A", mnemonic),
+            (OperandKind::Combo, 5) => write!(f, "{} B", mnemonic),
+            (OperandKind::Combo, 6) => write!(f, "{} C", mnemonic),
+            (_, operand) => write!(f, "{} {}", mnemonic, operand),
+        }
+    }
+}
 
-    while instruction_pointer < program.len() - 1 {
-        let opcode = program[instruction_pointer];
-        let operand = program[instruction_pointer + 1];
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionError {
+    InvalidOpcode { address: usize, opcode: u64 },
+    InvalidOperand { address: usize, operand: u64 },
+    InstructionLimit { limit: usize },
+}
 
-        match opcode {
-            0 => {
-                memory.register_a >>= combo_operand(memory, operand);
-                instruction_pointer += 2;
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionError::InvalidOpcode { address, opcode } => {
+                write!(f, "invalid opcode {} at address {}", opcode, address)
             }
-            1 => {
-                memory.register_b ^= operand;
-                instruction_pointer += 2;
+            ExecutionError::InvalidOperand { address, operand } => {
+                write!(
+                    f,
+                    "invalid combo operand {} at address {}",
+                    operand, address
+                )
             }
-            2 => {
-                memory.register_b = combo_operand(memory, operand) & 7;
-                instruction_pointer += 2;
+            ExecutionError::InstructionLimit { limit } => {
+                write!(f, "instruction limit of {} exceeded", limit)
             }
-            3 => {
-                if memory.register_a != 0 {
-                    instruction_pointer = operand as usize;
-                } else {
-                    instruction_pointer += 2;
+        }
+    }
+}
+
+impl Error for ExecutionError {}
+
+fn decode(program: &[u64], address: usize) -> Result<Instruction, ExecutionError> {
+    let opcode = program[address];
+    let operand = program[address + 1];
+
+    Opcode::from_code(opcode)
+        .map(|opcode| Instruction { opcode, operand })
+        .ok_or(ExecutionError::InvalidOpcode { address, opcode })
+}
+
+pub fn disassemble(program: &[u64]) -> String {
+    program
+        .chunks(2)
+        .map(|chunk| match chunk {
+            [opcode, operand] => match Opcode::from_code(*opcode) {
+                Some(opcode) => Instruction {
+                    opcode,
+                    operand: *operand,
                 }
+                .to_string(),
+                None => format!("??? {} {}", opcode, operand),
+            },
+            [value] => format!("??? {}", value),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssemblyErrorKind {
+    UnknownMnemonic(String),
+    MissingOperand,
+    UnexpectedToken(String),
+    InvalidOperand(String),
+    InvalidComboOperand(u64),
+    OperandOutOfRange(u64),
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UnknownLabel(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub kind: AssemblyErrorKind,
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            AssemblyErrorKind::UnknownMnemonic(mnemonic) => {
+                write!(f, "unknown mnemonic `{}`", mnemonic)
             }
-            4 => {
-                memory.register_b ^= memory.register_c;
-                instruction_pointer += 2;
+            AssemblyErrorKind::MissingOperand => write!(f, "missing operand"),
+            AssemblyErrorKind::UnexpectedToken(token) => write!(f, "unexpected `{}`", token),
+            AssemblyErrorKind::InvalidOperand(operand) => {
+                write!(f, "invalid operand `{}`", operand)
             }
-            5 => {
-                output.push(combo_operand(memory, operand) & 7);
-                instruction_pointer += 2;
+            AssemblyErrorKind::InvalidComboOperand(operand) => {
+                write!(f, "invalid combo operand {}", operand)
             }
-            6 => {
-                memory.register_b = memory.register_a >> combo_operand(memory, operand);
-                instruction_pointer += 2;
+            AssemblyErrorKind::OperandOutOfRange(operand) => {
+                write!(f, "operand {} does not fit in 3 bits", operand)
             }
-            7 => {
-                memory.register_c = memory.register_a >> combo_operand(memory, operand);
-                instruction_pointer += 2;
+            AssemblyErrorKind::InvalidLabel(label) => write!(f, "invalid label `{}`", label),
+            AssemblyErrorKind::DuplicateLabel(label) => {
+                write!(f, "label `{}` is already defined", label)
+            }
+            AssemblyErrorKind::UnknownLabel(label) => write!(f, "unknown label `{}`", label),
+        }
+    }
+}
+
+impl Error for AssemblyError {}
+
+struct SourceInstruction<'a> {
+    line: usize,
+    opcode: Opcode,
+    operand: Option<&'a str>,
+}
+
+fn is_label(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_operand(
+    instruction: &SourceInstruction,
+    labels: &HashMap<&str, u64>,
+) -> Result<u64, AssemblyErrorKind> {
+    let kind = instruction.opcode.operand_kind();
+
+    let Some(operand) = instruction.operand else {
+        return match kind {
+            OperandKind::Ignored => Ok(0),
+            _ => Err(AssemblyErrorKind::MissingOperand),
+        };
+    };
+
+    let value = match (kind, operand) {
+        (OperandKind::Combo, "A") => 4,
+        (OperandKind::Combo, "B") => 5,
+        (OperandKind::Combo, "C") => 6,
+        (_, operand) => match operand.parse::<u64>() {
+            Ok(value) => value,
+            Err(_) if kind == OperandKind::Literal && is_label(operand) => *labels
+                .get(operand)
+                .ok_or_else(|| AssemblyErrorKind::UnknownLabel(operand.to_string()))?,
+            Err(_) => return Err(AssemblyErrorKind::InvalidOperand(operand.to_string())),
+        },
+    };
+
+    if value > 7 {
+        Err(AssemblyErrorKind::OperandOutOfRange(value))
+    } else if kind == OperandKind::Combo && value == 7 {
+        Err(AssemblyErrorKind::InvalidComboOperand(value))
+    } else {
+        Ok(value)
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Vec<u64>, AssemblyError> {
+    let mut labels = HashMap::new();
+    let mut instructions = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |kind| AssemblyError {
+            line: line_number,
+            kind,
+        };
+
+        let mut code = line.split(';').next().unwrap().trim();
+
+        while let Some((label, rest)) = code.split_once(':') {
+            let label = label.trim();
+
+            if !is_label(label) {
+                return Err(error(AssemblyErrorKind::InvalidLabel(label.to_string())));
+            }
+
+            if labels
+                .insert(label, 2 * instructions.len() as u64)
+                .is_some()
+            {
+                return Err(error(AssemblyErrorKind::DuplicateLabel(label.to_string())));
+            }
+
+            code = rest.trim();
+        }
+
+        let mut tokens = code.split_whitespace();
+
+        let Some(mnemonic) = tokens.next() else {
+            continue;
+        };
+
+        let opcode = OPCODES
+            .into_iter()
+            .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(mnemonic))
+            .ok_or_else(|| error(AssemblyErrorKind::UnknownMnemonic(mnemonic.to_string())))?;
+
+        let operand = tokens.next();
+
+        if let Some(token) = tokens.next() {
+            return Err(error(AssemblyErrorKind::UnexpectedToken(token.to_string())));
+        }
+
+        instructions.push(SourceInstruction {
+            line: line_number,
+            opcode,
+            operand,
+        });
+    }
+
+    let mut program = Vec::with_capacity(2 * instructions.len());
+
+    for instruction in &instructions {
+        let operand = parse_operand(instruction, &labels).map_err(|kind| AssemblyError {
+            line: instruction.line,
+            kind,
+        })?;
+
+        program.push(instruction.opcode.code());
+        program.push(operand);
+    }
+
+    Ok(program)
+}
+
+fn shift_right(value: u64, amount: u64) -> u64 {
+    u32::try_from(amount)
+        .ok()
+        .and_then(|amount| value.checked_shr(amount))
+        .unwrap_or(0)
+}
+
+trait RegisterValue: Clone {
+    fn constant(value: u64) -> Self;
+    fn shifted_right(self, amount: Self) -> Self;
+    fn xor_with(self, other: Self) -> Self;
+    fn low_bits(self) -> Self;
+}
+
+impl RegisterValue for u64 {
+    fn constant(value: u64) -> Self {
+        value
+    }
+
+    fn shifted_right(self, amount: Self) -> Self {
+        shift_right(self, amount)
+    }
+
+    fn xor_with(self, other: Self) -> Self {
+        self ^ other
+    }
+
+    fn low_bits(self) -> Self {
+        self & 7
+    }
+}
+
+enum Effect<V> {
+    Continue,
+    Jump { condition: V, target: usize },
+    Output(V),
+}
+
+fn dispatch<V: RegisterValue>(
+    program: &[u64],
+    address: usize,
+    registers: &mut [V; 3],
+) -> Result<(Instruction, Effect<V>), ExecutionError> {
+    let instruction = decode(program, address)?;
+    let operand = instruction.operand;
+    let [register_a, register_b, register_c] = registers.clone();
+
+    let combo = match operand {
+        literal if literal <= 3 => Ok(V::constant(literal)),
+        4 => Ok(register_a.clone()),
+        5 => Ok(register_b.clone()),
+        6 => Ok(register_c.clone()),
+        _ => Err(ExecutionError::InvalidOperand { address, operand }),
+    };
+
+    let effect = match instruction.opcode {
+        Opcode::Adv => {
+            registers[0] = register_a.shifted_right(combo?);
+            Effect::Continue
+        }
+        Opcode::Bxl => {
+            registers[1] = register_b.xor_with(V::constant(operand));
+            Effect::Continue
+        }
+        Opcode::Bst => {
+            registers[1] = combo?.low_bits();
+            Effect::Continue
+        }
+        Opcode::Jnz => Effect::Jump {
+            condition: register_a,
+            target: operand as usize,
+        },
+        Opcode::Bxc => {
+            registers[1] = register_b.xor_with(register_c);
+            Effect::Continue
+        }
+        Opcode::Out => Effect::Output(combo?.low_bits()),
+        Opcode::Bdv => {
+            registers[1] = register_a.shifted_right(combo?);
+            Effect::Continue
+        }
+        Opcode::Cdv => {
+            registers[2] = register_a.shifted_right(combo?);
+            Effect::Continue
+        }
+    };
+
+    Ok((instruction, effect))
+}
+
+fn execute(
+    memory: &mut Memory,
+    program: &[u64],
+    instruction_pointer: usize,
+    output: &mut Vec<u64>,
+) -> Result<(Instruction, usize), ExecutionError> {
+    let mut registers = [memory.register_a, memory.register_b, memory.register_c];
+    let (instruction, effect) = dispatch(program, instruction_pointer, &mut registers)?;
+    let mut next_instruction_pointer = instruction_pointer + 2;
+
+    match effect {
+        Effect::Continue => (),
+        Effect::Jump { condition, target } => {
+            if condition != 0 {
+                next_instruction_pointer = target;
+            }
+        }
+        Effect::Output(value) => output.push(value),
+    }
+
+    [memory.register_a, memory.register_b, memory.register_c] = registers;
+
+    Ok((instruction, next_instruction_pointer))
+}
+
+fn run_program(memory: &mut Memory, program: &[u64]) -> Result<Vec<u64>, ExecutionError> {
+    let mut debugger =
+        Debugger::new(memory.clone(), program).with_instruction_limit(INSTRUCTION_LIMIT);
+    let result = debugger.run();
+
+    *memory = debugger.memory;
+    result?;
+
+    Ok(debugger.output)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub address: usize,
+    pub instruction: Instruction,
+    pub memory: Memory,
+    pub output: Option<u64>,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>3}: {:<6} A={} B={} C={}",
+            self.address,
+            self.instruction.to_string(),
+            self.memory.register_a,
+            self.memory.register_b,
+            self.memory.register_c
+        )?;
+
+        if let Some(output) = self.output {
+            write!(f, " out={}", output)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Halted,
+    Breakpoint(usize),
+}
+
+pub struct Debugger<'a> {
+    memory: Memory,
+    program: &'a [u64],
+    instruction_pointer: usize,
+    output: Vec<u64>,
+    breakpoints: HashSet<usize>,
+    paused_at: Option<usize>,
+    executed: usize,
+    instruction_limit: Option<usize>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(memory: Memory, program: &'a [u64]) -> Self {
+        Debugger {
+            memory,
+            program,
+            instruction_pointer: 0,
+            output: Vec::new(),
+            breakpoints: HashSet::new(),
+            paused_at: None,
+            executed: 0,
+            instruction_limit: None,
+        }
+    }
+
+    pub fn with_instruction_limit(mut self, instruction_limit: usize) -> Self {
+        self.instruction_limit = Some(instruction_limit);
+        self
+    }
+
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: usize) {
+        self.breakpoints.remove(&address);
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn output(&self) -> &[u64] {
+        &self.output
+    }
+
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.instruction_pointer + 1 >= self.program.len()
+    }
+
+    pub fn step(&mut self) -> Result<Option<TraceEntry>, ExecutionError> {
+        if self.is_halted() {
+            return Ok(None);
+        }
+
+        if let Some(limit) = self.instruction_limit {
+            if self.executed >= limit {
+                return Err(ExecutionError::InstructionLimit { limit });
+            }
+        }
+
+        let address = self.instruction_pointer;
+        let output_length = self.output.len();
+
+        let (instruction, next_instruction_pointer) =
+            execute(&mut self.memory, self.program, address, &mut self.output)?;
+
+        self.instruction_pointer = next_instruction_pointer;
+        self.paused_at = None;
+        self.executed += 1;
+
+        Ok(Some(TraceEntry {
+            address,
+            instruction,
+            memory: self.memory.clone(),
+            output: self.output.get(output_length).copied(),
+        }))
+    }
+
+    fn pause_at_breakpoint(&mut self) -> bool {
+        let address = self.instruction_pointer;
+
+        if self.paused_at == Some(address) || !self.breakpoints.contains(&address) {
+            return false;
+        }
+
+        self.paused_at = Some(address);
+        true
+    }
+
+    pub fn run(&mut self) -> Result<StopReason, ExecutionError> {
+        loop {
+            if self.pause_at_breakpoint() {
+                return Ok(StopReason::Breakpoint(self.instruction_pointer));
+            }
+
+            if self.step()?.is_none() {
+                return Ok(StopReason::Halted);
             }
-            _ => unreachable!(),
         }
     }
 
-    output
+    pub fn trace(&mut self) -> Result<Vec<TraceEntry>, ExecutionError> {
+        let mut trace = Vec::new();
+
+        while !self.pause_at_breakpoint() {
+            match self.step()? {
+                Some(entry) => trace.push(entry),
+                None => break,
+            }
+        }
+
+        Ok(trace)
+    }
 }
 
 fn print_output(output: &[u64]) -> String {
@@ -109,60 +633,612 @@ fn parse_input(input: &str) -> (Memory, Vec<u64>) {
 }
 
 #[aoc(day17, part1)]
-fn part1((memory, program): &(Memory, Vec<u64>)) -> String {
-    print_output(&run_program(&mut memory.clone(), program))
+fn part1((memory, program): &(Memory, Vec<u64>)) -> Result<String, ExecutionError> {
+    run_program(&mut memory.clone(), program).map(|output| print_output(&output))
 }
 
-fn search_a(memory: &mut Memory, iteration: usize, program: &[u64]) -> Option<u64> {
-    for remainder in 0..8 {
-        let multiplier = 8u64.pow(iteration as u32);
+const INSTRUCTION_LIMIT: usize = 1_000_000;
 
-        if memory.register_a + multiplier * remainder < 8u64.pow(program.len() as u32 - 1) {
-            continue;
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Expression {
+    Constant(u64),
+    InitialA,
+    ShiftRight(Box<Expression>, Box<Expression>),
+    Xor(Vec<Expression>),
+    And(Box<Expression>, u64),
+}
 
-        let result = run_program(
-            &mut Memory {
-                register_a: memory.register_a + multiplier * remainder,
-                register_b: memory.register_b,
-                register_c: memory.register_c,
+impl Expression {
+    fn possible_bits(&self) -> u64 {
+        match self {
+            Expression::Constant(value) => *value,
+            Expression::InitialA => !0,
+            Expression::ShiftRight(value, amount) => match **amount {
+                Expression::Constant(amount) => shift_right(value.possible_bits(), amount),
+                _ => value.possible_bits(),
             },
-            program,
-        );
+            Expression::Xor(terms) => terms
+                .iter()
+                .fold(0, |bits, term| bits | term.possible_bits()),
+            Expression::And(value, mask) => value.possible_bits() & mask,
+        }
+    }
 
-        if result[iteration] == program[iteration] {
-            return if iteration == 0 {
-                Some(memory.register_a + multiplier * remainder)
-            } else if let Some(register_a) = search_a(
-                &mut Memory {
-                    register_a: memory.register_a + multiplier * remainder,
-                    register_b: memory.register_b,
-                    register_c: memory.register_c,
+    fn shift_right(self, amount: Expression) -> Expression {
+        match (self, amount) {
+            (_, Expression::Constant(amount)) if amount >= 64 => Expression::Constant(0),
+            (value, Expression::Constant(0)) => value,
+            (Expression::Constant(value), Expression::Constant(amount)) => {
+                Expression::Constant(value >> amount)
+            }
+            (Expression::ShiftRight(value, inner), Expression::Constant(amount))
+                if matches!(*inner, Expression::Constant(_)) =>
+            {
+                let Expression::Constant(inner) = *inner else {
+                    unreachable!()
+                };
+
+                value.shift_right(Expression::Constant(inner.saturating_add(amount)))
+            }
+            (Expression::And(value, mask), Expression::Constant(amount)) => value
+                .shift_right(Expression::Constant(amount))
+                .and(mask >> amount),
+            (Expression::Xor(terms), Expression::Constant(amount)) => terms
+                .into_iter()
+                .fold(Expression::Constant(0), |shifted, term| {
+                    shifted.xor(term.shift_right(Expression::Constant(amount)))
+                }),
+            (value, _) if value.possible_bits() == 0 => Expression::Constant(0),
+            (value, amount) => Expression::ShiftRight(Box::new(value), Box::new(amount)),
+        }
+    }
+
+    fn xor(self, other: Expression) -> Expression {
+        let mut constant = 0;
+        let mut terms = Vec::new();
+
+        for term in [self, other] {
+            match term {
+                Expression::Xor(inner) => terms.extend(inner),
+                term => terms.push(term),
+            }
+        }
+
+        terms.retain(|term| match term {
+            Expression::Constant(value) => {
+                constant ^= value;
+                false
+            }
+            _ => true,
+        });
+        terms.sort();
+
+        let mut reduced: Vec<Expression> = Vec::new();
+
+        for term in terms {
+            if reduced.last() == Some(&term) {
+                reduced.pop();
+            } else {
+                reduced.push(term);
+            }
+        }
+
+        if constant != 0 {
+            reduced.push(Expression::Constant(constant));
+        }
+
+        match reduced.len() {
+            0 => Expression::Constant(0),
+            1 => reduced.pop().unwrap(),
+            _ => Expression::Xor(reduced),
+        }
+    }
+
+    fn and(self, mask: u64) -> Expression {
+        let possible_bits = self.possible_bits();
+
+        if possible_bits & mask == 0 {
+            return Expression::Constant(0);
+        }
+
+        if possible_bits & !mask == 0 {
+            return self;
+        }
+
+        match self {
+            Expression::Constant(value) => Expression::Constant(value & mask),
+            Expression::And(value, inner) => value.and(inner & mask),
+            Expression::Xor(terms) => terms
+                .into_iter()
+                .fold(Expression::Constant(0), |masked, term| {
+                    masked.xor(term.and(mask))
+                }),
+            value => Expression::And(Box::new(value), mask),
+        }
+    }
+
+    pub fn evaluate(&self, register_a: u64) -> u64 {
+        match self {
+            Expression::Constant(value) => *value,
+            Expression::InitialA => register_a,
+            Expression::ShiftRight(value, amount) => {
+                shift_right(value.evaluate(register_a), amount.evaluate(register_a))
+            }
+            Expression::Xor(terms) => terms
+                .iter()
+                .fold(0, |value, term| value ^ term.evaluate(register_a)),
+            Expression::And(value, mask) => value.evaluate(register_a) & mask,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Constant(_) | Expression::InitialA => write!(f, "{}", self),
+            _ => write!(f, "({})", self),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Constant(value) => write!(f, "{}", value),
+            Expression::InitialA => write!(f, "A"),
+            Expression::ShiftRight(value, amount) => {
+                value.fmt_operand(f)?;
+                write!(f, " >> ")?;
+                amount.fmt_operand(f)
+            }
+            Expression::Xor(terms) => {
+                for (index, term) in terms.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ^ ")?;
+                    }
+
+                    term.fmt_operand(f)?;
+                }
+
+                Ok(())
+            }
+            Expression::And(value, mask) => {
+                value.fmt_operand(f)?;
+                write!(f, " & {}", mask)
+            }
+        }
+    }
+}
+
+impl RegisterValue for Expression {
+    fn constant(value: u64) -> Self {
+        Expression::Constant(value)
+    }
+
+    fn shifted_right(self, amount: Self) -> Self {
+        self.shift_right(amount)
+    }
+
+    fn xor_with(self, other: Self) -> Self {
+        self.xor(other)
+    }
+
+    fn low_bits(self) -> Self {
+        self.and(7)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub expression: Expression,
+    pub is_zero: bool,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comparison = if self.is_zero { "==" } else { "!=" };
+
+        write!(f, "{} {} 0", self.expression, comparison)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SymbolicPath {
+    pub conditions: Vec<Condition>,
+    pub outputs: Vec<Expression>,
+    pub truncated: bool,
+}
+
+impl SymbolicPath {
+    pub fn is_satisfied_by(&self, register_a: u64) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| (condition.expression.evaluate(register_a) == 0) == condition.is_zero)
+    }
+
+    pub fn evaluate(&self, register_a: u64) -> Vec<u64> {
+        self.outputs
+            .iter()
+            .map(|output| output.evaluate(register_a))
+            .collect()
+    }
+}
+
+impl fmt::Display for SymbolicPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for condition in &self.conditions {
+            writeln!(f, "if {}", condition)?;
+        }
+
+        for output in &self.outputs {
+            writeln!(f, "out {}", output)?;
+        }
+
+        if self.truncated {
+            writeln!(f, "...")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SymbolicState {
+    registers: [Expression; 3],
+    instruction_pointer: usize,
+    path: SymbolicPath,
+}
+
+fn explore(
+    program: &[u64],
+    max_outputs: usize,
+    state: SymbolicState,
+) -> Result<Vec<SymbolicPath>, ExecutionError> {
+    let mut steps = 0;
+    let mut pending = vec![state];
+    let mut paths = Vec::new();
+
+    'branches: while let Some(mut state) = pending.pop() {
+        while state.instruction_pointer + 1 < program.len() {
+            if steps >= INSTRUCTION_LIMIT {
+                return Err(ExecutionError::InstructionLimit {
+                    limit: INSTRUCTION_LIMIT,
+                });
+            }
+
+            steps += 1;
+
+            let (_, effect) = dispatch(program, state.instruction_pointer, &mut state.registers)?;
+
+            state.instruction_pointer += 2;
+
+            match effect {
+                Effect::Continue => (),
+                Effect::Jump { condition, target } => match condition {
+                    Expression::Constant(0) => (),
+                    Expression::Constant(_) => state.instruction_pointer = target,
+                    expression => match state
+                        .path
+                        .conditions
+                        .iter()
+                        .find(|condition| condition.expression == expression)
+                    {
+                        Some(condition) if condition.is_zero => (),
+                        Some(_) => state.instruction_pointer = target,
+                        None => {
+                            let mut jumped = state.clone();
+                            jumped.instruction_pointer = target;
+                            jumped.path.conditions.push(Condition {
+                                expression: expression.clone(),
+                                is_zero: false,
+                            });
+                            pending.push(jumped);
+
+                            state.path.conditions.push(Condition {
+                                expression,
+                                is_zero: true,
+                            });
+                        }
+                    },
                 },
-                iteration - 1,
-                program,
-            ) {
-                Some(register_a)
+                Effect::Output(output) => {
+                    if state.path.outputs.len() == max_outputs {
+                        state.path.truncated = true;
+                        paths.push(state.path);
+                        continue 'branches;
+                    }
+
+                    state.path.outputs.push(output);
+                }
+            }
+        }
+
+        paths.push(state.path);
+    }
+
+    Ok(paths)
+}
+
+pub fn run_symbolic(
+    memory: &Memory,
+    program: &[u64],
+    max_outputs: usize,
+) -> Result<Vec<SymbolicPath>, ExecutionError> {
+    let state = SymbolicState {
+        registers: [
+            Expression::InitialA,
+            Expression::Constant(memory.register_b),
+            Expression::Constant(memory.register_c),
+        ],
+        instruction_pointer: 0,
+        path: SymbolicPath::default(),
+    };
+
+    let mut paths = explore(program, max_outputs, state)?;
+
+    paths.sort_by_key(|path| (path.outputs.len(), path.truncated));
+
+    Ok(paths)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitPattern {
+    pub known: u64,
+    pub value: u64,
+}
+
+impl BitPattern {
+    fn constant(value: u64) -> Self {
+        BitPattern { known: !0, value }
+    }
+
+    fn unknown() -> Self {
+        BitPattern { known: 0, value: 0 }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.known == !0
+    }
+
+    pub fn min(&self) -> u64 {
+        self.value
+    }
+
+    pub fn max(&self) -> u64 {
+        self.value | !self.known
+    }
+
+    pub fn matches(&self, value: u64) -> bool {
+        value & self.known == self.value
+    }
+
+    pub fn count(&self) -> u128 {
+        1 << (!self.known).count_ones()
+    }
+
+    fn with_bit(self, bit: u32, set: bool) -> Self {
+        BitPattern {
+            known: self.known | 1 << bit,
+            value: self.value | u64::from(set) << bit,
+        }
+    }
+
+    fn join(self, other: Self) -> Self {
+        let known = self.known & other.known & !(self.value ^ other.value);
+
+        BitPattern {
+            known,
+            value: self.value & known,
+        }
+    }
+
+    fn xor(self, other: Self) -> Self {
+        let known = self.known & other.known;
+
+        BitPattern {
+            known,
+            value: (self.value ^ other.value) & known,
+        }
+    }
+
+    fn low_bits(self) -> Self {
+        BitPattern {
+            known: self.known | !7,
+            value: self.value & 7,
+        }
+    }
+
+    fn shift_right(self, amount: Self) -> Self {
+        if amount.value >= 64 {
+            return BitPattern::constant(0);
+        }
+
+        let free = !amount.known & 63;
+        let mut shifted = if !amount.known & !63 != 0 {
+            Some(BitPattern::constant(0))
+        } else {
+            None
+        };
+        let mut subset = 0;
+
+        loop {
+            let shift = amount.value | subset;
+            let candidate = if shift >= 64 {
+                BitPattern::constant(0)
             } else {
-                continue;
+                BitPattern {
+                    known: self.known >> shift | !(!0 >> shift),
+                    value: self.value >> shift,
+                }
             };
+
+            shifted = Some(shifted.map_or(candidate, |shifted| shifted.join(candidate)));
+            subset = subset.wrapping_sub(free) & free;
+
+            if subset == 0 {
+                break;
+            }
         }
+
+        shifted.unwrap()
+    }
+}
+
+impl RegisterValue for BitPattern {
+    fn constant(value: u64) -> Self {
+        BitPattern::constant(value)
     }
 
-    None
+    fn shifted_right(self, amount: Self) -> Self {
+        self.shift_right(amount)
+    }
+
+    fn xor_with(self, other: Self) -> Self {
+        self.xor(other)
+    }
+
+    fn low_bits(self) -> Self {
+        BitPattern::low_bits(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Verdict {
+    Impossible,
+    Possible,
+    Certain,
 }
 
+#[derive(Debug, Clone)]
+struct AbstractState {
+    registers: [BitPattern; 3],
+    instruction_pointer: usize,
+    output_length: usize,
+    certain: bool,
+}
+
+fn evaluate(
+    program: &[u64],
+    expected_output: &[u64],
+    state: AbstractState,
+) -> Result<Verdict, ExecutionError> {
+    let mut steps = 0;
+    let mut pending = vec![state];
+    let mut verdict = Verdict::Impossible;
+
+    'branches: while let Some(mut state) = pending.pop() {
+        while state.instruction_pointer + 1 < program.len() {
+            if steps >= INSTRUCTION_LIMIT {
+                return Err(ExecutionError::InstructionLimit {
+                    limit: INSTRUCTION_LIMIT,
+                });
+            }
+
+            steps += 1;
+
+            let (_, effect) = dispatch(program, state.instruction_pointer, &mut state.registers)?;
+
+            state.instruction_pointer += 2;
+
+            match effect {
+                Effect::Continue => (),
+                Effect::Jump { condition, target } => {
+                    if condition.value != 0 {
+                        state.instruction_pointer = target;
+                    } else if !condition.is_known() {
+                        let mut jumped = state.clone();
+                        jumped.instruction_pointer = target;
+                        jumped.certain = false;
+                        pending.push(jumped);
+
+                        state.registers[0] = BitPattern::constant(0);
+                        state.certain = false;
+                    }
+                }
+                Effect::Output(output) => match expected_output.get(state.output_length) {
+                    Some(expected) if output.matches(*expected) => {
+                        state.output_length += 1;
+                        state.certain &= output.is_known();
+                    }
+                    _ => continue 'branches,
+                },
+            }
+        }
+
+        verdict = verdict.max(if state.output_length != expected_output.len() {
+            Verdict::Impossible
+        } else if state.certain {
+            Verdict::Certain
+        } else {
+            Verdict::Possible
+        });
+    }
+
+    Ok(verdict)
+}
+
+pub fn solve_register_a(
+    memory: &Memory,
+    program: &[u64],
+    expected_output: &[u64],
+) -> Result<Vec<BitPattern>, ExecutionError> {
+    let mut solutions = Vec::new();
+    let mut pending = vec![(BitPattern::unknown(), 0)];
+
+    while let Some((pattern, bit)) = pending.pop() {
+        let state = AbstractState {
+            registers: [
+                pattern,
+                BitPattern::constant(memory.register_b),
+                BitPattern::constant(memory.register_c),
+            ],
+            instruction_pointer: 0,
+            output_length: 0,
+            certain: true,
+        };
+
+        match evaluate(program, expected_output, state)? {
+            Verdict::Impossible => (),
+            Verdict::Certain => solutions.push(pattern),
+            Verdict::Possible => {
+                pending.push((pattern.with_bit(bit, true), bit + 1));
+                pending.push((pattern.with_bit(bit, false), bit + 1));
+            }
+        }
+    }
+
+    solutions.sort_by_key(BitPattern::min);
+
+    Ok(solutions)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveError {
+    Execution(ExecutionError),
+    NoSolution,
+}
+
+impl From<ExecutionError> for SolveError {
+    fn from(error: ExecutionError) -> Self {
+        SolveError::Execution(error)
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Execution(error) => write!(f, "{}", error),
+            SolveError::NoSolution => write!(f, "no initial A reproduces the expected output"),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
 #[aoc(day17, part2)]
-fn part2((_memory, program): &(Memory, Vec<u64>)) -> Option<u64> {
-    search_a(
-        &mut Memory {
-            register_a: 0,
-            register_b: 0,
-            register_c: 0,
-        },
-        program.len() - 1,
-        program,
-    )
+fn part2((memory, program): &(Memory, Vec<u64>)) -> Result<u64, SolveError> {
+    solve_register_a(memory, program, program)?
+        .first()
+        .map(BitPattern::min)
+        .ok_or(SolveError::NoSolution)
 }
 
 #[cfg(test)]
@@ -189,7 +1265,7 @@ Program: 0,3,5,4,3,0";
             register_c: 9,
         };
 
-        run_program(&mut memory, &[2, 6]);
+        run_program(&mut memory, &[2, 6]).unwrap();
 
         assert_eq!(memory.register_b, 1);
     }
@@ -203,7 +1279,7 @@ Program: 0,3,5,4,3,0";
         };
 
         assert_eq!(
-            print_output(&run_program(&mut memory, &[5, 0, 5, 1, 5, 4])),
+            print_output(&run_program(&mut memory, &[5, 0, 5, 1, 5, 4]).unwrap()),
             "0,1,2"
         );
     }
@@ -216,12 +1292,44 @@ Program: 0,3,5,4,3,0";
             register_c: 0,
         };
 
-        let output = run_program(&mut memory, &[0, 1, 5, 4, 3, 0]);
+        let output = run_program(&mut memory, &[0, 1, 5, 4, 3, 0]).unwrap();
 
         assert_eq!(print_output(&output), "4,2,5,6,7,7,7,7,3,1,0");
         assert_eq!(memory.register_a, 0);
     }
 
+    #[test]
+    fn part1_endless_program() {
+        let (memory, _) = parse_input(TEST_INPUT_1);
+
+        assert_eq!(
+            part1(&(memory, vec![1, 0, 3, 0])),
+            Err(ExecutionError::InstructionLimit {
+                limit: INSTRUCTION_LIMIT
+            })
+        );
+    }
+
+    #[test]
+    fn part1_invalid_operand() {
+        let (memory, _) = parse_input(TEST_INPUT_1);
+
+        assert_eq!(
+            part1(&(memory.clone(), vec![0, 7])),
+            Err(ExecutionError::InvalidOperand {
+                address: 0,
+                operand: 7
+            })
+        );
+        assert_eq!(
+            part1(&(memory, vec![5, 4, 8, 0])),
+            Err(ExecutionError::InvalidOpcode {
+                address: 2,
+                opcode: 8
+            })
+        );
+    }
+
     #[test]
     fn part1_program_example_4() {
         let mut memory = Memory {
@@ -230,7 +1338,7 @@ Program: 0,3,5,4,3,0";
             register_c: 0,
         };
 
-        run_program(&mut memory, &[1, 7]);
+        run_program(&mut memory, &[1, 7]).unwrap();
 
         assert_eq!(memory.register_b, 26);
     }
@@ -243,18 +1351,250 @@ Program: 0,3,5,4,3,0";
             register_c: 43_690,
         };
 
-        run_program(&mut memory, &[4, 0]);
+        run_program(&mut memory, &[4, 0]).unwrap();
 
         assert_eq!(memory.register_b, 44_354);
     }
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse_input(TEST_INPUT_1)), "4,6,3,5,6,3,5,2,1,0");
+        assert_eq!(
+            part1(&parse_input(TEST_INPUT_1)),
+            Ok("4,6,3,5,6,3,5,2,1,0".to_string())
+        );
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse_input(TEST_INPUT_2)), Some(117_440));
+        assert_eq!(part2(&parse_input(TEST_INPUT_2)), Ok(117_440));
+        assert_eq!(
+            part2(&parse_input(TEST_INPUT_1)),
+            Err(SolveError::NoSolution)
+        );
+
+        let (memory, _) = parse_input(TEST_INPUT_2);
+
+        assert_eq!(
+            part2(&(memory, vec![1, 0, 3, 0])),
+            Err(SolveError::Execution(ExecutionError::InstructionLimit {
+                limit: INSTRUCTION_LIMIT
+            }))
+        );
+    }
+
+    #[test]
+    fn disassembler() {
+        assert_eq!(
+            disassemble(&[2, 4, 1, 7, 7, 5, 0, 3, 4, 1, 5, 5, 3, 0]),
+            "bst A\nbxl 7\ncdv B\nadv 3\nbxc 1\nout B\njnz 0"
+        );
+    }
+
+    #[test]
+    fn debugger() {
+        let (memory, program) = parse_input(TEST_INPUT_1);
+        let mut debugger = Debugger::new(memory.clone(), &program);
+        debugger.add_breakpoint(4);
+
+        assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(4)));
+        assert_eq!(debugger.memory().register_a, 364);
+        assert_eq!(debugger.output(), [4]);
+
+        debugger.remove_breakpoint(4);
+
+        assert_eq!(debugger.run(), Ok(StopReason::Halted));
+        assert_eq!(print_output(debugger.output()), "4,6,3,5,6,3,5,2,1,0");
+
+        let mut debugger = Debugger::new(memory.clone(), &program);
+        debugger.add_breakpoint(0);
+
+        assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(0)));
+        assert_eq!(debugger.memory(), &memory);
+        assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(0)));
+        assert_eq!(debugger.memory().register_a, 364);
+        assert_eq!(debugger.output(), [4]);
+
+        let mut debugger = Debugger::new(memory.clone(), &program);
+        debugger.add_breakpoint(2);
+
+        assert_eq!(debugger.trace().unwrap().len(), 1);
+        assert_eq!(debugger.trace().unwrap().len(), 3);
+
+        debugger.remove_breakpoint(2);
+
+        assert_eq!(debugger.trace().unwrap().len(), 26);
+
+        let trace = Debugger::new(memory, &program).trace().unwrap();
+
+        assert_eq!(trace.len(), 30);
+        assert_eq!(trace[1].to_string(), "  2: out A  A=364 B=0 C=0 out=4");
+
+        let endless = [1, 0, 3, 0];
+
+        assert_eq!(
+            Debugger::new(trace[0].memory.clone(), &endless)
+                .with_instruction_limit(1_000)
+                .run(),
+            Err(ExecutionError::InstructionLimit { limit: 1_000 })
+        );
+        assert_eq!(
+            Debugger::new(trace[0].memory.clone(), &[0, 7]).step(),
+            Err(ExecutionError::InvalidOperand {
+                address: 0,
+                operand: 7
+            })
+        );
+    }
+
+    #[test]
+    fn assembler() {
+        let source = "; prints A in octal, least significant digit first
+loop:
+    adv 1   ; shift
+    out A
+    jnz loop";
+
+        assert_eq!(assemble(source), Ok(vec![0, 1, 5, 4, 3, 0]));
+
+        let (_, program) = parse_input(TEST_INPUT_2);
+        let quine = [2, 4, 1, 7, 7, 5, 0, 3, 4, 1, 5, 5, 3, 0];
+
+        assert_eq!(assemble(&disassemble(&program)), Ok(program));
+        assert_eq!(assemble(&disassemble(&quine)), Ok(quine.to_vec()));
+
+        assert_eq!(
+            assemble("adv 1\nbst 7"),
+            Err(AssemblyError {
+                line: 2,
+                kind: AssemblyErrorKind::InvalidComboOperand(7)
+            })
+        );
+        assert_eq!(
+            assemble("start: bxl 8"),
+            Err(AssemblyError {
+                line: 1,
+                kind: AssemblyErrorKind::OperandOutOfRange(8)
+            })
+        );
+        assert_eq!(
+            assemble("jnz end"),
+            Err(AssemblyError {
+                line: 1,
+                kind: AssemblyErrorKind::UnknownLabel("end".to_string())
+            })
+        );
+        assert_eq!(
+            assemble("a: out A\na: jmp a"),
+            Err(AssemblyError {
+                line: 2,
+                kind: AssemblyErrorKind::DuplicateLabel("a".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn register_a_solver() {
+        let program = assemble(
+            "loop:
+                bst A
+                bxc
+                out B
+                bdv 1
+                out B
+                cdv 3
+                adv 2
+                jnz loop",
+        )
+        .unwrap();
+        let memory = Memory {
+            register_a: 1_234,
+            register_b: 0,
+            register_c: 5,
+        };
+        let expected_output = run_program(&mut memory.clone(), &program).unwrap();
+
+        let solutions = solve_register_a(&memory, &program, &expected_output).unwrap();
+        let brute_force = (0..1 << 12)
+            .filter(|register_a| {
+                run_program(
+                    &mut Memory {
+                        register_a: *register_a,
+                        ..memory.clone()
+                    },
+                    &program,
+                ) == Ok(expected_output.clone())
+            })
+            .collect::<Vec<_>>();
+
+        assert!(brute_force.contains(&1_234));
+        assert_eq!(
+            solutions.iter().map(BitPattern::count).sum::<u128>(),
+            brute_force.len() as u128
+        );
+        assert!(brute_force
+            .iter()
+            .all(|register_a| solutions.iter().any(|pattern| pattern.matches(*register_a))));
+
+        let shifting = assemble("adv 3\nout A\njnz 0").unwrap();
+
+        assert_eq!(solve_register_a(&memory, &shifting, &[0, 0]), Ok(vec![]));
+        assert_eq!(
+            solve_register_a(&memory, &[1, 0, 3, 0], &[]),
+            Err(ExecutionError::InstructionLimit {
+                limit: INSTRUCTION_LIMIT
+            })
+        );
+        assert_eq!(
+            solve_register_a(&memory, &[5, 6], &[5]),
+            Ok(vec![BitPattern { known: 0, value: 0 }])
+        );
+    }
+
+    #[test]
+    fn symbolic_execution() {
+        let (memory, program) = parse_input(TEST_INPUT_2);
+        let paths = run_symbolic(&memory, &program, program.len()).unwrap();
+
+        assert_eq!(paths.len(), program.len() + 1);
+        assert!(paths[program.len()].truncated);
+        assert_eq!(
+            paths[1].to_string(),
+            "if A >> 3 != 0\nif A >> 6 == 0\nout (A >> 3) & 7\nout (A >> 6) & 7\n"
+        );
+        assert!(paths[5].is_satisfied_by(117_440));
+        assert_eq!(paths[5].evaluate(117_440), program);
+
+        let program = [2, 4, 1, 1, 7, 5, 1, 5, 4, 0, 5, 5, 0, 3, 3, 0];
+        let paths = run_symbolic(&memory, &program, 2).unwrap();
+
+        assert_eq!(
+            paths[0].outputs[0].to_string(),
+            "(A & 7) ^ ((A >> ((A & 7) ^ 1)) & 7) ^ 4"
+        );
+
+        for register_a in [1, 59, 4_095, 123_456_789] {
+            let output = run_program(
+                &mut Memory {
+                    register_a,
+                    ..memory.clone()
+                },
+                &program,
+            )
+            .unwrap();
+            let path = paths.iter().find(|path| path.is_satisfied_by(register_a));
+
+            let path = path.unwrap();
+
+            assert_eq!(path.truncated, output.len() > 2);
+            assert_eq!(path.evaluate(register_a), output[..output.len().min(2)]);
+        }
+
+        assert!(paths[2].to_string().ends_with("...\n"));
+        assert_eq!(
+            run_symbolic(&memory, &[1, 0, 3, 0], 10),
+            Err(ExecutionError::InstructionLimit {
+                limit: INSTRUCTION_LIMIT
+            })
+        );
     }
 }
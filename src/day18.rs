@@ -1,68 +1,189 @@
+use crate::disjoint_set::DisjointSet;
+use crate::search::{bfs, SearchProblem};
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::{HashMap, HashSet, VecDeque};
 
+type Position = (i32, i32);
+
+const ORTHOGONAL: [Position; 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const DIAGONAL: [Position; 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
 #[aoc_generator(day18)]
-fn parse_input(input: &str) -> Vec<(i32, i32)> {
+fn parse_input(input: &str) -> Vec<Position> {
     use aoc_parse::{parser, prelude::*};
 
     let parser = parser!(lines(i32 "," i32));
     parser.parse(input).unwrap()
 }
 
-fn shortest_path(
-    corrupted_locations: &[(i32, i32)],
-    exit: (i32, i32),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLayout {
+    start: Position,
+    exit: Position,
+    min: Position,
+    max: Position,
+    diagonal_moves: bool,
+}
+
+impl MemoryLayout {
+    pub fn new(start: Position, exit: Position, min: Position, max: Position) -> Option<Self> {
+        (min.0 <= max.0 && min.1 <= max.1).then_some(MemoryLayout {
+            start,
+            exit,
+            min,
+            max,
+            diagonal_moves: false,
+        })
+    }
+
+    pub fn square(exit: Position) -> Option<Self> {
+        MemoryLayout::new((0, 0), exit, (0, 0), exit)
+    }
+
+    pub fn with_diagonal_moves(self) -> Self {
+        MemoryLayout {
+            diagonal_moves: true,
+            ..self
+        }
+    }
+
+    fn width(&self) -> usize {
+        (self.max.0 - self.min.0 + 1) as usize
+    }
+
+    fn height(&self) -> usize {
+        (self.max.1 - self.min.1 + 1) as usize
+    }
+
+    fn contains(&self, position: &Position) -> bool {
+        position.0 >= self.min.0
+            && position.0 <= self.max.0
+            && position.1 >= self.min.1
+            && position.1 <= self.max.1
+    }
+
+    fn cell(&self, position: &Position) -> usize {
+        (position.1 - self.min.1) as usize * self.width() + (position.0 - self.min.0) as usize
+    }
+
+    fn neighbours(&self, position: &Position) -> impl Iterator<Item = Position> + '_ {
+        let (x, y) = *position;
+        let diagonal_count = if self.diagonal_moves {
+            DIAGONAL.len()
+        } else {
+            0
+        };
+
+        ORTHOGONAL
+            .iter()
+            .chain(DIAGONAL.iter().take(diagonal_count))
+            .map(move |direction| (x + direction.0, y + direction.1))
+            .filter(|neighbour| self.contains(neighbour))
+    }
+
+    pub fn render(&self, corrupted_locations: &[Position], path: &[Position]) -> String {
+        let corrupted_locations: HashSet<&Position> = corrupted_locations.iter().collect();
+        let path: HashSet<&Position> = path.iter().collect();
+
+        (self.min.1..=self.max.1)
+            .map(|y| {
+                (self.min.0..=self.max.0)
+                    .map(|x| {
+                        if corrupted_locations.contains(&(x, y)) {
+                            '#'
+                        } else if path.contains(&(x, y)) {
+                            'O'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn first_fall_indexes(corrupted_locations: &[Position]) -> HashMap<Position, usize> {
+    corrupted_locations
+        .iter()
+        .enumerate()
+        .fold(HashMap::new(), |mut indexes, (index, location)| {
+            indexes.entry(*location).or_insert(index);
+            indexes
+        })
+}
+
+struct MemorySpace<'a> {
+    byte_indexes: &'a HashMap<Position, usize>,
     bytes: usize,
-) -> Option<usize> {
-    let corrupted_locations: HashSet<_> = corrupted_locations.iter().take(bytes).collect();
-
-    let mut queue = VecDeque::from([((0, 0), 0)]);
-    let mut visited = HashSet::from([(0, 0)]);
-
-    while let Some((position, distance)) = queue.pop_front() {
-        for next_position in [
-            (position.0 + 1, position.1),
-            (position.0 - 1, position.1),
-            (position.0, position.1 + 1),
-            (position.0, position.1 - 1),
-        ] {
-            if next_position.0 >= 0
-                && next_position.0 <= exit.0
-                && next_position.1 >= 0
-                && next_position.1 <= exit.1
-            {
-                if next_position == exit {
-                    return Some(distance + 1);
-                }
+    layout: &'a MemoryLayout,
+}
 
-                if !corrupted_locations.contains(&next_position)
-                    && !visited.contains(&next_position)
-                {
-                    queue.push_back((next_position, distance + 1));
-                    visited.insert(next_position);
-                }
-            }
+impl MemorySpace<'_> {
+    fn is_corrupted(&self, position: &Position) -> bool {
+        self.byte_indexes
+            .get(position)
+            .is_some_and(|index| *index < self.bytes)
+    }
+}
+
+impl SearchProblem for MemorySpace<'_> {
+    type State = Position;
+    type Cost = usize;
+
+    fn starts(&self) -> Vec<Position> {
+        if self.layout.contains(&self.layout.start) && !self.is_corrupted(&self.layout.start) {
+            vec![self.layout.start]
+        } else {
+            Vec::new()
         }
     }
 
-    None
+    fn successors(&self, position: &Position) -> Vec<(Position, usize)> {
+        self.layout
+            .neighbours(position)
+            .filter(|next_position| !self.is_corrupted(next_position))
+            .map(|next_position| (next_position, 1))
+            .collect()
+    }
+
+    fn is_goal(&self, position: &Position) -> bool {
+        *position == self.layout.exit
+    }
+}
+
+pub fn find_path(
+    corrupted_locations: &[Position],
+    layout: &MemoryLayout,
+    bytes: usize,
+) -> Option<Vec<Position>> {
+    let memory_space = MemorySpace {
+        byte_indexes: &first_fall_indexes(corrupted_locations),
+        bytes,
+        layout,
+    };
+
+    bfs(&memory_space).map(|path| path.states)
+}
+
+fn shortest_path(corrupted_locations: &[Position], exit: Position, bytes: usize) -> Option<usize> {
+    find_path(corrupted_locations, &MemoryLayout::square(exit)?, bytes).map(|path| path.len() - 1)
 }
 
 #[aoc(day18, part1)]
-fn part1(corrupted_locations: &[(i32, i32)]) -> Option<usize> {
+fn part1(corrupted_locations: &[Position]) -> Option<usize> {
     shortest_path(corrupted_locations, (70, 70), 1024)
 }
 
-fn blocking_byte(corrupted_locations: &[(i32, i32)], exit: (i32, i32)) -> Option<(i32, i32)> {
-    let byte_indexes = corrupted_locations.iter().enumerate().fold(
-        HashMap::new(),
-        |mut indexes, (index, location)| {
-            indexes.entry(location).or_insert(index);
-            indexes
-        },
-    );
+fn blocking_byte(corrupted_locations: &[Position], layout: &MemoryLayout) -> Option<Position> {
+    if !layout.contains(&layout.start) || !layout.contains(&layout.exit) {
+        return None;
+    }
 
-    let start = (0, 0);
+    let byte_indexes = first_fall_indexes(corrupted_locations);
+
+    let start = layout.start;
     let starting_index = if let Some(index) = byte_indexes.get(&start) {
         *index
     } else {
@@ -76,40 +197,31 @@ fn blocking_byte(corrupted_locations: &[(i32, i32)], exit: (i32, i32)) -> Option
     let mut max_index = 0;
 
     while let Some((position, index)) = queue.pop_front() {
-        for next_position in [
-            (position.0 + 1, position.1),
-            (position.0 - 1, position.1),
-            (position.0, position.1 + 1),
-            (position.0, position.1 - 1),
-        ] {
-            if next_position.0 >= 0
-                && next_position.0 <= exit.0
-                && next_position.1 >= 0
-                && next_position.1 <= exit.1
+        for next_position in layout.neighbours(&position) {
+            if next_position == layout.exit
+                && index >= max_index
+                && index < corrupted_locations.len()
             {
-                if next_position == exit && index >= max_index && index < corrupted_locations.len()
-                {
-                    blocking_byte = Some(corrupted_locations[index]);
-                    max_index = index;
-                }
+                blocking_byte = Some(corrupted_locations[index]);
+                max_index = index;
+            }
 
-                if let Some(prev_index) = visited.get(&next_position) {
-                    if *prev_index >= index {
-                        continue;
-                    }
+            if let Some(prev_index) = visited.get(&next_position) {
+                if *prev_index >= index {
+                    continue;
                 }
+            }
+
+            match byte_indexes.get(&next_position) {
+                Some(corrupted_index) if *corrupted_index < index => {
+                    let new_index = *corrupted_index;
 
-                match byte_indexes.get(&next_position) {
-                    Some(corrupted_index) if *corrupted_index < index => {
-                        let new_index = *corrupted_index;
-
-                        queue.push_back((next_position, new_index));
-                        visited.insert(next_position, new_index);
-                    }
-                    _ => {
-                        queue.push_back((next_position, index));
-                        visited.insert(next_position, index);
-                    }
+                    queue.push_back((next_position, new_index));
+                    visited.insert(next_position, new_index);
+                }
+                _ => {
+                    queue.push_back((next_position, index));
+                    visited.insert(next_position, index);
                 }
             }
         }
@@ -119,11 +231,135 @@ fn blocking_byte(corrupted_locations: &[(i32, i32)], exit: (i32, i32)) -> Option
 }
 
 #[aoc(day18, part2)]
-fn part2(corrupted_locations: &[(i32, i32)]) -> String {
-    let (x, y) = blocking_byte(corrupted_locations, (70, 70)).unwrap();
+fn part2(corrupted_locations: &[Position]) -> String {
+    let (x, y) = blocking_byte(
+        corrupted_locations,
+        &MemoryLayout::square((70, 70)).unwrap(),
+    )
+    .unwrap();
+    format!("{},{}", x, y)
+}
+
+pub fn blocking_byte_union_find(
+    corrupted_locations: &[Position],
+    layout: &MemoryLayout,
+) -> Option<Position> {
+    if !layout.contains(&layout.start) || !layout.contains(&layout.exit) {
+        return None;
+    }
+
+    let byte_indexes = first_fall_indexes(corrupted_locations);
+    let cells_count = layout.width() * layout.height();
+
+    let mut free = vec![true; cells_count];
+    let mut cells = DisjointSet::new(cells_count);
+
+    for location in byte_indexes
+        .keys()
+        .filter(|location| layout.contains(location))
+    {
+        free[layout.cell(location)] = false;
+    }
+
+    let release = |free: &mut Vec<bool>, cells: &mut DisjointSet, position: &Position| {
+        free[layout.cell(position)] = true;
+
+        for neighbour in layout.neighbours(position) {
+            if free[layout.cell(&neighbour)] {
+                cells.union(layout.cell(position), layout.cell(&neighbour));
+            }
+        }
+    };
+
+    for y in layout.min.1..=layout.max.1 {
+        for x in layout.min.0..=layout.max.0 {
+            if free[layout.cell(&(x, y))] {
+                release(&mut free, &mut cells, &(x, y));
+            }
+        }
+    }
+
+    let (start, exit) = (layout.cell(&layout.start), layout.cell(&layout.exit));
+    let connected = |free: &[bool], cells: &mut DisjointSet| {
+        free[start] && free[exit] && cells.find(start) == cells.find(exit)
+    };
+
+    if connected(&free, &mut cells) {
+        return None;
+    }
+
+    for (index, location) in corrupted_locations.iter().enumerate().rev() {
+        if byte_indexes[location] != index || !layout.contains(location) {
+            continue;
+        }
+
+        release(&mut free, &mut cells, location);
+
+        if connected(&free, &mut cells) {
+            return Some(*location);
+        }
+    }
+
+    None
+}
+
+#[aoc(day18, part2, union_find)]
+fn part2_union_find(corrupted_locations: &[Position]) -> String {
+    let (x, y) = blocking_byte_union_find(
+        corrupted_locations,
+        &MemoryLayout::square((70, 70)).unwrap(),
+    )
+    .unwrap();
     format!("{},{}", x, y)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteStatus {
+    pub byte: Position,
+    pub shortest_path: Option<usize>,
+}
+
+impl ByteStatus {
+    pub fn is_reachable(&self) -> bool {
+        self.shortest_path.is_some()
+    }
+}
+
+pub fn byte_timeline(corrupted_locations: &[Position], layout: &MemoryLayout) -> Vec<ByteStatus> {
+    let byte_indexes = first_fall_indexes(corrupted_locations);
+    let mut memory_space = MemorySpace {
+        byte_indexes: &byte_indexes,
+        bytes: 0,
+        layout,
+    };
+
+    let mut path = bfs(&memory_space);
+    let mut path_positions: HashSet<Position> = path
+        .iter()
+        .flat_map(|path| path.states.iter().copied())
+        .collect();
+
+    corrupted_locations
+        .iter()
+        .enumerate()
+        .map(|(index, byte)| {
+            if path_positions.contains(byte) {
+                memory_space.bytes = index + 1;
+                path = bfs(&memory_space);
+                path_positions = path
+                    .iter()
+                    .flat_map(|path| path.states.iter().copied())
+                    .collect();
+            }
+
+            ByteStatus {
+                byte: *byte,
+                shortest_path: path.as_ref().map(|path| path.cost),
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,6 +400,94 @@ mod tests {
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse_input(TEST_INPUT)), "6,1");
+        assert_eq!(
+            blocking_byte(
+                &parse_input(TEST_INPUT),
+                &MemoryLayout::square((6, 6)).unwrap()
+            ),
+            Some((6, 1))
+        );
+    }
+
+    #[test]
+    fn part2_union_find_example() {
+        assert_eq!(
+            blocking_byte_union_find(
+                &parse_input(TEST_INPUT),
+                &MemoryLayout::square((6, 6)).unwrap()
+            ),
+            Some((6, 1))
+        );
+
+        let outside = MemoryLayout::new((0, 0), (7, 6), (0, 0), (6, 6)).unwrap();
+
+        assert_eq!(
+            blocking_byte_union_find(&parse_input(TEST_INPUT), &outside),
+            None
+        );
+        assert_eq!(blocking_byte(&parse_input(TEST_INPUT), &outside), None);
+        assert_eq!(MemoryLayout::new((0, 0), (6, 6), (6, 0), (0, 6)), None);
+        assert_eq!(MemoryLayout::square((-1, 6)), None);
+        assert_eq!(find_path(&parse_input(TEST_INPUT), &outside, 0), None);
+    }
+
+    #[test]
+    fn timeline_example() {
+        let corrupted_locations = parse_input(TEST_INPUT);
+        let timeline = byte_timeline(&corrupted_locations, &MemoryLayout::square((6, 6)).unwrap());
+
+        assert_eq!(timeline.len(), corrupted_locations.len());
+        assert_eq!(timeline[11].shortest_path, Some(22));
+        assert!(timeline[19].is_reachable());
+        assert_eq!(timeline[20].byte, (6, 1));
+        assert!(!timeline[20].is_reachable());
+
+        for (index, status) in timeline.iter().enumerate() {
+            assert_eq!(
+                status.shortest_path,
+                shortest_path(&corrupted_locations, (6, 6), index + 1)
+            );
+        }
+    }
+
+    #[test]
+    fn generalised_layouts() {
+        let corrupted_locations = parse_input(TEST_INPUT);
+        let layout = MemoryLayout::square((6, 6)).unwrap();
+        let path = find_path(&corrupted_locations, &layout, 12).unwrap();
+
+        assert_eq!(path.len(), 23);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(6, 6)));
+        assert!(path
+            .windows(2)
+            .all(|step| (step[0].0 - step[1].0).abs() + (step[0].1 - step[1].1).abs() == 1));
+        assert_eq!(
+            layout.render(&corrupted_locations[..12], &path),
+            "OO.#OOO\n.O#OO#O\n.OOO#OO\n...#OO#\n..#OO#.\n.#.O#..\n#.#OOOO"
+        );
+
+        let diagonal = layout.with_diagonal_moves();
+
+        assert_eq!(
+            find_path(&corrupted_locations, &diagonal, 12).map(|path| path.len() - 1),
+            Some(7)
+        );
+
+        let rectangle = MemoryLayout::new((5, -2), (-1, 1), (-1, -2), (5, 1)).unwrap();
+        let wall = [(2, -2), (2, -1), (2, 0)];
+
+        assert_eq!(
+            find_path(&wall, &rectangle, 3).map(|path| path.len() - 1),
+            Some(9)
+        );
+        assert_eq!(
+            blocking_byte(&[(2, -2), (2, -1), (2, 0), (2, 1)], &rectangle),
+            Some((2, 1))
+        );
+        assert_eq!(
+            blocking_byte_union_find(&[(2, -2), (2, -1), (2, 0), (2, 1)], &rectangle),
+            Some((2, 1))
+        );
     }
 }
@@ -1,83 +1,209 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
-#[aoc_generator(day19)]
-fn parse_input(input: &str) -> (HashSet<String>, Vec<String>) {
-    use aoc_parse::{parser, prelude::*};
-
-    let parser = parser!(
-        section(hash_set(line(repeat_sep(string({'w', 'u', 'b', 'r', 'g'}+), ", ")))) 
-        section(lines(string({'w', 'u', 'b', 'r', 'g'}+))));
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<char, usize>,
+    towel: Option<usize>,
+}
 
-    parser.parse(input).unwrap()
+#[derive(Debug)]
+pub struct TowelSet {
+    towels: Vec<String>,
+    nodes: Vec<TrieNode>,
 }
 
-fn is_possible(pattern: &str, towels: &HashSet<String>) -> bool {
-    let mut possible = false;
+impl TowelSet {
+    pub fn new<S: AsRef<str>>(towels: impl IntoIterator<Item = S>) -> Self {
+        let mut towel_set = TowelSet {
+            towels: Vec::new(),
+            nodes: vec![TrieNode::default()],
+        };
+
+        for towel in towels {
+            towel_set.insert(towel.as_ref());
+        }
 
-    if pattern.is_empty() {
-        return true;
+        towel_set
     }
 
-    for i in 1..=pattern.len() {
-        let prefix = &pattern[0..i];
-        let postfix = &pattern[i..];
+    fn insert(&mut self, towel: &str) {
+        if towel.is_empty() {
+            return;
+        }
+
+        let mut node = 0;
 
-        possible = possible || towels.contains(prefix) && is_possible(postfix, towels);
+        for colour in towel.chars() {
+            node = match self.nodes[node].children.get(&colour) {
+                Some(child) => *child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(colour, child);
+                    child
+                }
+            };
+        }
 
-        if possible {
-            return true;
+        if self.nodes[node].towel.is_none() {
+            self.nodes[node].towel = Some(self.towels.len());
+            self.towels.push(towel.to_string());
         }
     }
 
-    possible
-}
+    pub fn towels(&self) -> &[String] {
+        &self.towels
+    }
 
-#[aoc(day19, part1)]
-fn part1((towels, patterns): &(HashSet<String>, Vec<String>)) -> usize {
-    patterns
-        .iter()
-        .filter(|pattern| is_possible(pattern, towels))
-        .count()
-}
+    fn matches(&self, pattern: &[char], start: usize) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut node = 0;
+
+        for (end, colour) in pattern.iter().enumerate().skip(start) {
+            match self.nodes[node].children.get(colour) {
+                Some(child) => node = *child,
+                None => break,
+            }
 
-fn count_possibilities(
-    pattern: &str,
-    towels: &HashSet<String>,
-    index: &mut HashMap<String, usize>,
-) -> usize {
-    if pattern.is_empty() {
-        return 1;
+            if let Some(towel) = self.nodes[node].towel {
+                matches.push((end + 1, towel));
+            }
+        }
+
+        matches
+    }
+
+    fn arrangement_counts(&self, pattern: &[char]) -> Option<Vec<u128>> {
+        let mut counts = vec![0u128; pattern.len() + 1];
+        counts[pattern.len()] = 1;
+
+        for start in (0..pattern.len()).rev() {
+            counts[start] = self
+                .matches(pattern, start)
+                .into_iter()
+                .try_fold(0u128, |count, (end, _)| count.checked_add(counts[end]))?;
+        }
+
+        Some(counts)
     }
 
-    if let Some(possibilities) = index.get(pattern) {
-        return *possibilities;
+    pub fn count_arrangements(&self, pattern: &str) -> Option<u128> {
+        let pattern: Vec<char> = pattern.chars().collect();
+
+        self.arrangement_counts(&pattern).map(|counts| counts[0])
     }
 
-    let mut possibilities = 0;
+    pub fn is_possible(&self, pattern: &str) -> bool {
+        self.min_towels(pattern).is_some()
+    }
 
-    for i in 1..=pattern.len() {
-        let prefix = &pattern[0..i];
-        let postfix = &pattern[i..];
+    fn fewest_towels(&self, pattern: &[char]) -> Vec<Option<(usize, usize, usize)>> {
+        let mut fewest = vec![None; pattern.len() + 1];
+        fewest[pattern.len()] = Some((0, pattern.len(), 0));
 
-        if towels.contains(prefix) {
-            possibilities += count_possibilities(postfix, towels, index);
+        for start in (0..pattern.len()).rev() {
+            fewest[start] = self
+                .matches(pattern, start)
+                .into_iter()
+                .filter_map(|(end, towel)| fewest[end].map(|(count, _, _)| (count + 1, end, towel)))
+                .min();
         }
+
+        fewest
+    }
+
+    pub fn min_towels(&self, pattern: &str) -> Option<usize> {
+        let pattern: Vec<char> = pattern.chars().collect();
+
+        self.fewest_towels(&pattern)[0].map(|(count, _, _)| count)
     }
 
-    index.insert(pattern.to_string(), possibilities);
+    pub fn arrangement(&self, pattern: &str) -> Option<Vec<&str>> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let fewest = self.fewest_towels(&pattern);
+
+        let mut arrangement = Vec::new();
+        let mut start = 0;
 
-    possibilities
+        while start < pattern.len() {
+            let (_, end, towel) = fewest[start]?;
+
+            arrangement.push(self.towels[towel].as_str());
+            start = end;
+        }
+
+        Some(arrangement)
+    }
+
+    pub fn arrangements(&self, pattern: &str) -> Vec<Vec<&str>> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let possible: Vec<bool> = self
+            .fewest_towels(&pattern)
+            .iter()
+            .map(Option::is_some)
+            .collect();
+
+        let mut arrangements = Vec::new();
+
+        if possible[0] {
+            self.collect_arrangements(&pattern, 0, &possible, &mut Vec::new(), &mut arrangements);
+        }
+
+        arrangements
+    }
+
+    fn collect_arrangements<'a>(
+        &'a self,
+        pattern: &[char],
+        start: usize,
+        possible: &[bool],
+        arrangement: &mut Vec<&'a str>,
+        arrangements: &mut Vec<Vec<&'a str>>,
+    ) {
+        if start == pattern.len() {
+            arrangements.push(arrangement.clone());
+            return;
+        }
+
+        for (end, towel) in self.matches(pattern, start) {
+            if possible[end] {
+                arrangement.push(&self.towels[towel]);
+                self.collect_arrangements(pattern, end, possible, arrangement, arrangements);
+                arrangement.pop();
+            }
+        }
+    }
 }
 
-#[aoc(day19, part2)]
-fn part2((towels, patterns): &(HashSet<String>, Vec<String>)) -> usize {
-    let mut index = HashMap::new();
+#[aoc_generator(day19)]
+fn parse_input(input: &str) -> (TowelSet, Vec<String>) {
+    let (towels, patterns) = input.split_once("\n\n").unwrap();
+
+    (
+        TowelSet::new(towels.split(',').map(str::trim)),
+        patterns
+            .lines()
+            .filter(|pattern| !pattern.is_empty())
+            .map(String::from)
+            .collect(),
+    )
+}
 
+#[aoc(day19, part1)]
+fn part1((towels, patterns): &(TowelSet, Vec<String>)) -> usize {
     patterns
         .iter()
-        .map(|pattern| count_possibilities(pattern, towels, &mut index))
-        .sum()
+        .filter(|pattern| towels.is_possible(pattern))
+        .count()
+}
+
+#[aoc(day19, part2)]
+fn part2((towels, patterns): &(TowelSet, Vec<String>)) -> Option<u128> {
+    patterns.iter().try_fold(0u128, |total, pattern| {
+        total.checked_add(towels.count_arrangements(pattern)?)
+    })
 }
 
 #[cfg(test)]
@@ -102,6 +228,47 @@ bbrgwb";
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse_input(TEST_INPUT)), 16);
+        assert_eq!(part2(&parse_input(TEST_INPUT)), Some(16));
+    }
+
+    #[test]
+    fn arrangements_example() {
+        let (towels, _) = parse_input(TEST_INPUT);
+
+        assert_eq!(towels.min_towels("rrbgbr"), Some(4));
+        assert_eq!(towels.min_towels("ubwu"), None);
+        assert_eq!(towels.arrangement("brwrr"), Some(vec!["br", "wr", "r"]));
+        assert_eq!(towels.arrangement("bbrgwb"), None);
+        assert_eq!(
+            towels.arrangements("gbbr"),
+            vec![
+                vec!["g", "b", "b", "r"],
+                vec!["g", "b", "br"],
+                vec!["gb", "b", "r"],
+                vec!["gb", "br"],
+            ]
+        );
+    }
+
+    #[test]
+    fn arbitrary_alphabet() {
+        let (towels, patterns) = parse_input("α, αβ, 1, β1\n\nαβ1\nαα11\n1β");
+
+        assert_eq!(
+            patterns
+                .iter()
+                .map(|pattern| towels.count_arrangements(pattern))
+                .collect::<Vec<_>>(),
+            vec![Some(2), Some(1), Some(0)]
+        );
+
+        let unit = TowelSet::new(["a", "aa"]);
+
+        assert_eq!(
+            unit.count_arrangements(&"a".repeat(150)),
+            Some(16_130_531_424_904_581_415_797_907_386_349)
+        );
+        assert_eq!(unit.count_arrangements(&"a".repeat(200)), None);
+        assert_eq!(unit.min_towels(&"a".repeat(200)), Some(100));
     }
 }
@@ -1,5 +1,6 @@
+use crate::search::{distances, distances_to_goals, ReversibleProblem, SearchProblem};
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 type Position = (i32, i32);
 
@@ -7,7 +8,7 @@ const DEPRECATED_CHEAT_DURATION: i32 = 2;
 const LATEST_CHEAT_DURATION: i32 = 20;
 
 #[derive(Debug)]
-struct RaceSetup {
+pub struct RaceSetup {
     obstacles: HashSet<Position>,
     start: Position,
     end: Position,
@@ -46,85 +47,194 @@ fn distance(position_a: &Position, position_b: &Position) -> i32 {
     (position_b.0 - position_a.0).abs() + (position_b.1 - position_a.1).abs()
 }
 
-fn count_deprecated_cheats(race_setup: &RaceSetup, saved: i32) -> usize {
-    let mut path = HashMap::from([(race_setup.start, 0)]);
-    let mut position = race_setup.start;
+impl SearchProblem for RaceSetup {
+    type State = Position;
+    type Cost = i32;
+
+    fn starts(&self) -> Vec<Position> {
+        vec![self.start]
+    }
 
-    while position != race_setup.end {
-        for next_position in [
+    fn successors(&self, position: &Position) -> Vec<(Position, i32)> {
+        [
             (position.0 - 1, position.1),
             (position.0 + 1, position.1),
             (position.0, position.1 - 1),
             (position.0, position.1 + 1),
-        ] {
-            if !path.contains_key(&next_position) && !race_setup.obstacles.contains(&next_position)
-            {
-                path.insert(next_position, *path.get(&position).unwrap() + 1);
-                position = next_position;
-            }
-        }
+        ]
+        .into_iter()
+        .filter(|next_position| !self.obstacles.contains(next_position))
+        .map(|next_position| (next_position, 1))
+        .collect()
     }
 
-    let mut count = 0;
+    fn is_goal(&self, position: &Position) -> bool {
+        *position == self.end
+    }
+}
 
-    for (position, cost) in path.iter() {
-        for next_position in [
-            (position.0 - 2, position.1),
-            (position.0 + 2, position.1),
-            (position.0, position.1 - 2),
-            (position.0, position.1 + 2),
-        ] {
-            if let Some(next_cost) = path.get(&next_position) {
-                if next_cost - cost >= saved + DEPRECATED_CHEAT_DURATION {
-                    count += 1;
-                }
-            }
-        }
+impl ReversibleProblem for RaceSetup {
+    fn goals(&self) -> Vec<Position> {
+        vec![self.end]
+    }
+
+    fn predecessors(&self, position: &Position) -> Vec<(Position, i32)> {
+        self.successors(position)
     }
+}
 
-    count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cheat {
+    pub start: Position,
+    pub end: Position,
+    pub saving: i32,
 }
 
-#[aoc(day20, part1)]
-fn part1(race_setup: &RaceSetup) -> usize {
-    count_deprecated_cheats(race_setup, 100)
+struct DistanceGrid {
+    min: Position,
+    width: i32,
+    height: i32,
+    distances: Vec<Option<i32>>,
 }
 
-fn count_latest_cheats(race_setup: &RaceSetup, saved: i32) -> usize {
-    let mut path = vec![race_setup.start];
-    let mut visited = HashSet::from([race_setup.start]);
-    let mut position = race_setup.start;
+impl DistanceGrid {
+    fn new(distances: &HashMap<Position, i32>, min: Position, max: Position) -> Self {
+        let (width, height) = (max.0 - min.0 + 1, max.1 - min.1 + 1);
+        let mut grid = vec![None; (width * height) as usize];
 
-    while position != race_setup.end {
-        for next_position in [
-            (position.0 - 1, position.1),
-            (position.0 + 1, position.1),
-            (position.0, position.1 - 1),
-            (position.0, position.1 + 1),
-        ] {
-            if !visited.contains(&next_position) && !race_setup.obstacles.contains(&next_position) {
-                visited.insert(next_position);
-                path.push(next_position);
-                position = next_position;
-            }
+        for (position, distance) in distances {
+            grid[((position.1 - min.1) * width + position.0 - min.0) as usize] = Some(*distance);
         }
+
+        DistanceGrid {
+            min,
+            width,
+            height,
+            distances: grid,
+        }
+    }
+
+    fn get(&self, position: &Position) -> Option<i32> {
+        let (x, y) = (position.0 - self.min.0, position.1 - self.min.1);
+
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            None
+        } else {
+            self.distances[(y * self.width + x) as usize]
+        }
+    }
+}
+
+pub struct CheatAnalyser {
+    track: Vec<(Position, i32)>,
+    to_end: DistanceGrid,
+    race_time: i32,
+}
+
+impl CheatAnalyser {
+    pub fn new(race_setup: &RaceSetup) -> Option<Self> {
+        let from_start = distances(race_setup);
+        let race_time = *from_start.get(&race_setup.end)?;
+        let to_end = distances_to_goals(race_setup);
+
+        let (min, max) =
+            to_end
+                .keys()
+                .fold((race_setup.end, race_setup.end), |(min, max), position| {
+                    (
+                        (min.0.min(position.0), min.1.min(position.1)),
+                        (max.0.max(position.0), max.1.max(position.1)),
+                    )
+                });
+
+        let mut track: Vec<(Position, i32)> = from_start.into_iter().collect();
+        track.sort_unstable();
+
+        Some(CheatAnalyser {
+            track,
+            to_end: DistanceGrid::new(&to_end, min, max),
+            race_time,
+        })
     }
 
-    let mut count = 0;
+    pub fn race_time(&self) -> i32 {
+        self.race_time
+    }
 
-    for cheat_start_index in 0..path.len() {
-        for cheat_end_index in cheat_start_index + 1..path.len() {
-            let distance = distance(&path[cheat_start_index], &path[cheat_end_index]);
+    fn for_each_cheat(&self, duration: i32, min_saving: i32, mut visit: impl FnMut(Cheat)) {
+        let min_saving = min_saving.max(1);
 
-            if distance <= LATEST_CHEAT_DURATION
-                && (cheat_end_index - cheat_start_index) as i32 >= saved + distance
-            {
-                count += 1;
+        for (start, start_cost) in &self.track {
+            if start_cost + min_saving > self.race_time {
+                continue;
+            }
+
+            for delta_x in -duration..=duration {
+                let remaining = duration - delta_x.abs();
+
+                for delta_y in -remaining..=remaining {
+                    let end = (start.0 + delta_x, start.1 + delta_y);
+
+                    if let Some(end_cost) = self.to_end.get(&end) {
+                        let saving =
+                            self.race_time - (start_cost + distance(start, &end) + end_cost);
+
+                        if saving >= min_saving {
+                            visit(Cheat {
+                                start: *start,
+                                end,
+                                saving,
+                            });
+                        }
+                    }
+                }
             }
         }
     }
 
-    count
+    pub fn cheats(&self, duration: i32, min_saving: i32) -> Vec<Cheat> {
+        let mut cheats = Vec::new();
+
+        self.for_each_cheat(duration, min_saving, |cheat| cheats.push(cheat));
+
+        cheats.sort_unstable_by_key(|cheat| (cheat.saving, cheat.start, cheat.end));
+        cheats
+    }
+
+    pub fn count_cheats(&self, duration: i32, min_saving: i32) -> usize {
+        let mut count = 0;
+
+        self.for_each_cheat(duration, min_saving, |_| count += 1);
+
+        count
+    }
+
+    pub fn savings_histogram(&self, duration: i32, min_saving: i32) -> BTreeMap<i32, usize> {
+        let mut histogram = BTreeMap::new();
+
+        self.for_each_cheat(duration, min_saving, |cheat| {
+            *histogram.entry(cheat.saving).or_insert(0) += 1;
+        });
+
+        histogram
+    }
+}
+
+fn count_deprecated_cheats(race_setup: &RaceSetup, saved: i32) -> usize {
+    CheatAnalyser::new(race_setup).map_or(0, |analyser| {
+        analyser.count_cheats(DEPRECATED_CHEAT_DURATION, saved)
+    })
+}
+
+#[aoc(day20, part1)]
+fn part1(race_setup: &RaceSetup) -> usize {
+    count_deprecated_cheats(race_setup, 100)
+}
+
+fn count_latest_cheats(race_setup: &RaceSetup, saved: i32) -> usize {
+    CheatAnalyser::new(race_setup).map_or(0, |analyser| {
+        analyser.count_cheats(LATEST_CHEAT_DURATION, saved)
+    })
 }
 
 #[aoc(day20, part2)]
@@ -339,4 +449,94 @@ mod tests {
     fn part2_example14() {
         assert_eq!(count_latest_cheats(&parse_input(TEST_INPUT), 76), 3);
     }
+
+    #[test]
+    fn savings_histogram_example() {
+        let analyser = CheatAnalyser::new(&parse_input(TEST_INPUT)).unwrap();
+
+        assert_eq!(analyser.race_time(), 84);
+        assert_eq!(
+            analyser.savings_histogram(DEPRECATED_CHEAT_DURATION, 1),
+            BTreeMap::from([
+                (2, 14),
+                (4, 14),
+                (6, 2),
+                (8, 4),
+                (10, 2),
+                (12, 3),
+                (20, 1),
+                (36, 1),
+                (38, 1),
+                (40, 1),
+                (64, 1)
+            ])
+        );
+        assert_eq!(
+            analyser.cheats(DEPRECATED_CHEAT_DURATION, 64),
+            vec![Cheat {
+                start: (7, 7),
+                end: (5, 7),
+                saving: 64
+            }]
+        );
+        assert_eq!(
+            analyser.savings_histogram(LATEST_CHEAT_DURATION, 74),
+            BTreeMap::from([(74, 4), (76, 3)])
+        );
+    }
+
+    #[test]
+    fn branching_maze() {
+        let analyser = CheatAnalyser::new(&parse_input(
+            "#######\n#S....#\n#.###.#\n#.....#\n###.###\n###E###\n#######",
+        ))
+        .unwrap();
+
+        assert_eq!(analyser.race_time(), 6);
+        assert!(analyser.cheats(DEPRECATED_CHEAT_DURATION, 1).is_empty());
+
+        let analyser = CheatAnalyser::new(&parse_input(
+            "#########\n#S#.....#\n#.#.###.#\n#...#E..#\n#########",
+        ))
+        .unwrap();
+
+        assert_eq!(analyser.race_time(), 14);
+        assert_eq!(
+            analyser.savings_histogram(DEPRECATED_CHEAT_DURATION, 1),
+            BTreeMap::from([(2, 2), (4, 2), (8, 1)])
+        );
+        assert_eq!(
+            analyser.cheats(DEPRECATED_CHEAT_DURATION, 8),
+            vec![Cheat {
+                start: (3, 3),
+                end: (5, 3),
+                saving: 8
+            }]
+        );
+    }
+
+    #[test]
+    fn long_cheats() {
+        let race_setup = parse_input(TEST_INPUT);
+        let analyser = CheatAnalyser::new(&race_setup).unwrap();
+        let from_start = distances(&race_setup);
+        let to_end = distances_to_goals(&race_setup);
+
+        for duration in [20, 100] {
+            let pairwise = from_start
+                .iter()
+                .flat_map(|start| to_end.iter().map(move |end| (start, end)))
+                .filter(|((start, start_cost), (end, end_cost))| {
+                    let cheat_time = distance(start, end);
+
+                    cheat_time <= duration
+                        && analyser.race_time() - (*start_cost + cheat_time + *end_cost) >= 50
+                })
+                .count();
+
+            assert_eq!(analyser.count_cheats(duration, 50), pairwise);
+        }
+
+        assert_eq!(analyser.count_cheats(100, 1), analyser.cheats(100, 1).len());
+    }
 }
@@ -1,11 +1,14 @@
 use crate::day21::Button::*;
 use crate::day21::Direction::*;
 use aoc_runner_derive::{aoc, aoc_generator};
+use num::{BigInt, BigUint, One, Signed, Zero};
 use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
 use std::hash::Hash;
 
 #[derive(Eq, PartialEq, Copy, Clone, Debug, Hash)]
-enum Direction {
+pub enum Direction {
     Up,
     Down,
     Left,
@@ -13,25 +16,165 @@ enum Direction {
 }
 
 #[derive(Eq, PartialEq, Copy, Clone, Debug, Hash)]
-enum Button {
-    Number(u8),
+pub enum Button {
+    Key(char),
     Move(Direction),
     Activate,
 }
 
+impl From<char> for Button {
+    fn from(symbol: char) -> Self {
+        match symbol {
+            '^' => Move(Up),
+            'v' => Move(Down),
+            '<' => Move(Left),
+            '>' => Move(Right),
+            'A' => Activate,
+            symbol => Key(symbol),
+        }
+    }
+}
+
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key(symbol) => write!(f, "{}", symbol),
+            Move(Up) => write!(f, "^"),
+            Move(Down) => write!(f, "v"),
+            Move(Left) => write!(f, "<"),
+            Move(Right) => write!(f, ">"),
+            Activate => write!(f, "A"),
+        }
+    }
+}
+
 type Position = (i8, i8);
 
-#[aoc_generator(day21)]
-fn parse_input(input: &str) -> Vec<Vec<Button>> {
-    use aoc_parse::{parser, prelude::*};
-    use Button::*;
+const NUMERIC_LAYOUT: &str = "789\n456\n123\n 0A";
+const DIRECTIONAL_LAYOUT: &str = " ^A\n<v>";
 
-    let parser = parser!(lines({
-        number:digit => Number(number as u8), 
-        'A' => Activate
-    }+));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeypadError {
+    DuplicateKey(char),
+    MissingActivate,
+}
 
-    parser.parse(input).unwrap()
+impl fmt::Display for KeypadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeypadError::DuplicateKey(symbol) => write!(f, "key `{}` appears twice", symbol),
+            KeypadError::MissingActivate => write!(f, "keypad has no `A` key"),
+        }
+    }
+}
+
+impl Error for KeypadError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keypad {
+    keys: Vec<(Button, Position)>,
+}
+
+impl Keypad {
+    pub fn parse(layout: &str) -> Result<Self, KeypadError> {
+        let rows: Vec<Vec<Option<char>>> = if layout.contains('|') {
+            let border = layout.lines().find(|row| row.contains('+')).unwrap_or("");
+            let corners: Vec<usize> = border.match_indices('+').map(|(index, _)| index).collect();
+            let cell_width = corners
+                .windows(2)
+                .map(|pair| pair[1] - pair[0])
+                .next()
+                .unwrap_or(4);
+
+            layout
+                .lines()
+                .filter(|row| row.contains('|'))
+                .map(|row| {
+                    let separators: Vec<usize> =
+                        row.match_indices('|').map(|(index, _)| index).collect();
+                    let mut cells = vec![None; separators.last().unwrap_or(&0) / cell_width];
+
+                    for pair in separators.windows(2) {
+                        cells[pair[0] / cell_width] =
+                            row[pair[0] + 1..pair[1]].trim().chars().next();
+                    }
+
+                    cells
+                })
+                .collect()
+        } else {
+            layout
+                .lines()
+                .map(|row| {
+                    row.chars()
+                        .map(|symbol| Some(symbol).filter(|symbol| !symbol.is_whitespace()))
+                        .collect()
+                })
+                .collect()
+        };
+
+        let mut keys: Vec<(Button, Position)> = Vec::new();
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.iter().enumerate() {
+                if let Some(symbol) = symbol {
+                    let button = Button::from(*symbol);
+
+                    if keys.iter().any(|(key, _)| *key == button) {
+                        return Err(KeypadError::DuplicateKey(*symbol));
+                    }
+
+                    keys.push((button, (x as i8, y as i8)));
+                }
+            }
+        }
+
+        if !keys.iter().any(|(button, _)| *button == Activate) {
+            return Err(KeypadError::MissingActivate);
+        }
+
+        Ok(Keypad { keys })
+    }
+
+    pub fn numeric() -> Self {
+        Keypad::parse(NUMERIC_LAYOUT).unwrap()
+    }
+
+    pub fn directional() -> Self {
+        Keypad::parse(DIRECTIONAL_LAYOUT).unwrap()
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = Button> + '_ {
+        self.keys.iter().map(|(button, _)| *button)
+    }
+
+    fn position(&self, button: &Button) -> Option<Position> {
+        self.keys
+            .iter()
+            .find(|(key, _)| key == button)
+            .map(|(_, position)| *position)
+    }
+
+    fn button_at(&self, position: &Position) -> Option<Button> {
+        self.keys
+            .iter()
+            .find(|(_, key_position)| key_position == position)
+            .map(|(button, _)| *button)
+    }
+}
+
+pub fn standard_chain(robots_count: usize) -> Vec<Keypad> {
+    let mut keypads = vec![Keypad::numeric()];
+    keypads.extend((0..robots_count).map(|_| Keypad::directional()));
+    keypads
+}
+
+#[aoc_generator(day21)]
+fn parse_input(input: &str) -> Vec<Vec<Button>> {
+    input
+        .lines()
+        .map(|line| line.chars().map(Button::from).collect())
+        .collect()
 }
 
 fn move_in_direction(position: &Position, direction: &Direction) -> Position {
@@ -43,25 +186,12 @@ fn move_in_direction(position: &Position, direction: &Direction) -> Position {
     }
 }
 
-fn distance(position_1: &Position, position_2: &Position) -> i8 {
-    (position_1.0 - position_2.0).abs() + (position_1.1 - position_2.1).abs()
-}
-
-fn shortest_paths(
-    start_button: &Button,
-    end_button: &Button,
-    keypad: &[(Button, Position)],
-) -> Vec<Vec<Button>> {
-    let start_position = keypad
-        .iter()
-        .find(|(button, _)| button == start_button)
-        .unwrap()
-        .1;
-    let end_position = keypad
-        .iter()
-        .find(|(button, _)| button == end_button)
-        .unwrap()
-        .1;
+fn shortest_paths(start_button: &Button, end_button: &Button, keypad: &Keypad) -> Vec<Vec<Button>> {
+    let (Some(start_position), Some(end_position)) =
+        (keypad.position(start_button), keypad.position(end_button))
+    else {
+        return Vec::new();
+    };
 
     let mut distances: HashMap<Position, usize> = HashMap::from([(start_position, 0)]);
     let mut queue: VecDeque<(Position, usize)> = VecDeque::from([(start_position, 0)]);
@@ -74,9 +204,7 @@ fn shortest_paths(
         for direction in [Up, Down, Left, Right] {
             let new_position = move_in_direction(&position, &direction);
 
-            if keypad.iter().any(|(_, position)| *position == new_position)
-                && !distances.contains_key(&new_position)
-            {
+            if keypad.button_at(&new_position).is_some() && !distances.contains_key(&new_position) {
                 distances.insert(new_position, distance + 1);
                 queue.push_back((new_position, distance + 1));
             }
@@ -112,119 +240,487 @@ fn shortest_paths(
     paths
 }
 
-fn init_costs(levels: usize) -> HashMap<(Button, Button), usize> {
-    let directional_keypad = vec![
-        (Move(Up), (1, 0)),
-        (Activate, (2, 0)),
-        (Move(Left), (0, 1)),
-        (Move(Down), (1, 1)),
-        (Move(Right), (2, 1)),
-    ];
+type Costs = HashMap<(Button, Button), u128>;
 
-    let mut costs: HashMap<(Button, Button), usize> = HashMap::new();
-    let mut paths: HashMap<(Button, Button), Vec<Vec<Button>>> = HashMap::new();
+fn path_cost(path: &[Button], costs: &Costs) -> Option<u128> {
+    path.iter()
+        .try_fold((0u128, Activate), |(cost, prev_button), button| {
+            Some((
+                cost.checked_add(*costs.get(&(prev_button, *button))?)?,
+                *button,
+            ))
+        })
+        .map(|(cost, _)| cost)
+}
 
-    for (start_button, start_position) in directional_keypad.iter() {
-        for (end_button, end_position) in directional_keypad.iter() {
-            costs.insert(
-                (*start_button, *end_button),
-                distance(start_position, end_position) as usize + 1,
-            );
+fn cheapest_path(
+    start_button: &Button,
+    end_button: &Button,
+    keypad: &Keypad,
+    costs: &Costs,
+) -> Option<(u128, Vec<Button>)> {
+    shortest_paths(start_button, end_button, keypad)
+        .into_iter()
+        .filter_map(|path| path_cost(&path, costs).map(|cost| (cost, path)))
+        .min_by_key(|(cost, _)| *cost)
+}
 
-            paths.insert(
-                (*start_button, *end_button),
-                shortest_paths(start_button, end_button, &directional_keypad),
-            );
+fn chain_costs(keypads: &[Keypad]) -> Option<Vec<Costs>> {
+    let human_keypad = keypads.last()?;
+
+    let mut chain_costs: Vec<Costs> = vec![human_keypad
+        .buttons()
+        .flat_map(|start_button| {
+            human_keypad
+                .buttons()
+                .map(move |end_button| ((start_button, end_button), 1))
+        })
+        .collect()];
+
+    for keypad in keypads.iter().rev().skip(1) {
+        let costs = chain_costs.last().unwrap();
+        let mut new_costs: Costs = HashMap::new();
+
+        for start_button in keypad.buttons() {
+            for end_button in keypad.buttons() {
+                if let Some((cost, _)) = cheapest_path(&start_button, &end_button, keypad, costs) {
+                    new_costs.insert((start_button, end_button), cost);
+                }
+            }
         }
+
+        chain_costs.push(new_costs);
     }
 
-    for _ in 1..levels {
-        let mut new_costs: HashMap<(Button, Button), usize> = HashMap::new();
+    chain_costs.reverse();
+    Some(chain_costs)
+}
 
-        for (start_button, _) in directional_keypad.iter() {
-            for (end_button, _) in directional_keypad.iter() {
-                let paths = paths.get(&(*start_button, *end_button)).unwrap();
+pub fn sequence_len(code: &[Button], keypads: &[Keypad]) -> Option<u128> {
+    path_cost(code, &chain_costs(keypads)?[0])
+}
 
-                let new_cost = paths
-                    .iter()
-                    .map(|path| {
-                        path.iter()
-                            .fold((0, Activate), |(cost, prev_button), button| {
-                                (cost + costs.get(&(prev_button, *button)).unwrap(), *button)
-                            })
-                    })
-                    .map(|(cost, _)| cost)
-                    .min()
-                    .unwrap();
+fn shortest_sequence_len(code: &[Button], robots_count: usize) -> u128 {
+    sequence_len(code, &standard_chain(robots_count)).unwrap()
+}
 
-                new_costs.insert((*start_button, *end_button), new_cost);
-            }
+fn expand(sequence: &[Button], keypad: &Keypad, costs: &Costs) -> Option<Vec<Button>> {
+    sequence
+        .iter()
+        .try_fold(
+            (Vec::new(), Activate),
+            |(mut expanded, prev_button), button| {
+                expanded.extend(cheapest_path(&prev_button, button, keypad, costs)?.1);
+                Some((expanded, *button))
+            },
+        )
+        .map(|(expanded, _)| expanded)
+}
+
+pub fn shortest_sequence(code: &[Button], keypads: &[Keypad]) -> Option<Vec<Button>> {
+    let chain_costs = chain_costs(keypads)?;
+    let mut sequence = code.to_vec();
+
+    for (keypad, costs) in keypads.iter().zip(chain_costs.iter().skip(1)) {
+        sequence = expand(&sequence, keypad, costs)?;
+    }
+
+    Some(sequence)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RobotPanic {
+    ArmOffKeypad { keypad: usize, position: Position },
+    UnknownCommand { keypad: usize, button: Button },
+    EmptyChain,
+}
+
+impl fmt::Display for RobotPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RobotPanic::ArmOffKeypad { keypad, position } => write!(
+                f,
+                "robot at keypad {} moved its arm off the keypad at {:?}",
+                keypad, position
+            ),
+            RobotPanic::UnknownCommand { keypad, button } => write!(
+                f,
+                "robot at keypad {} cannot follow command `{}`",
+                keypad, button
+            ),
+            RobotPanic::EmptyChain => write!(f, "keypad chain is empty"),
         }
+    }
+}
 
-        costs = new_costs;
+impl Error for RobotPanic {}
+
+fn type_on_keypad(
+    sequence: &[Button],
+    keypads: &[Keypad],
+    index: usize,
+) -> Result<Vec<Button>, RobotPanic> {
+    let keypad = &keypads[index];
+    let mut position = keypad.position(&Activate).unwrap();
+    let mut typed = Vec::new();
+
+    for button in sequence {
+        match button {
+            Move(direction) => {
+                position = move_in_direction(&position, direction);
+
+                if keypad.button_at(&position).is_none() {
+                    return Err(RobotPanic::ArmOffKeypad {
+                        keypad: index,
+                        position,
+                    });
+                }
+            }
+            Activate => typed.push(keypad.button_at(&position).unwrap()),
+            Key(_) => {
+                return Err(RobotPanic::UnknownCommand {
+                    keypad: index,
+                    button: *button,
+                })
+            }
+        }
     }
 
-    costs
+    Ok(typed)
 }
 
-fn shortest_sequence_len(code: &[Button], robots_count: usize) -> usize {
-    let numeric_keypad = vec![
-        (Number(7), (0, 0)),
-        (Number(8), (1, 0)),
-        (Number(9), (2, 0)),
-        (Number(4), (0, 1)),
-        (Number(5), (1, 1)),
-        (Number(6), (2, 1)),
-        (Number(1), (0, 2)),
-        (Number(2), (1, 2)),
-        (Number(3), (2, 2)),
-        (Number(0), (1, 3)),
-        (Activate, (2, 3)),
-    ];
+pub fn simulate(sequence: &[Button], keypads: &[Keypad]) -> Result<Vec<Vec<Button>>, RobotPanic> {
+    if keypads.is_empty() {
+        return Err(RobotPanic::EmptyChain);
+    }
 
-    let costs = init_costs(robots_count - 1);
+    let mut typed_by_layer: Vec<Vec<Button>> = Vec::new();
 
-    code.iter()
-        .fold((0, Activate), |(cost, prev_button), button| {
-            let cost = cost
-                + shortest_paths(&prev_button, button, &numeric_keypad)
-                    .iter()
-                    .map(|path| {
-                        path.iter()
-                            .fold((0, Activate), |(cost, prev_button), button| {
-                                (cost + costs.get(&(prev_button, *button)).unwrap(), *button)
-                            })
-                    })
-                    .map(|(cost, _)| cost)
-                    .min()
-                    .unwrap();
+    for index in (0..keypads.len() - 1).rev() {
+        let typed = type_on_keypad(
+            typed_by_layer.last().map_or(sequence, |typed| typed),
+            keypads,
+            index,
+        )?;
 
-            (cost, *button)
-        })
-        .0
+        typed_by_layer.push(typed);
+    }
+
+    Ok(typed_by_layer)
 }
 
-fn numerical_value(code: &[Button]) -> usize {
+fn numerical_value(code: &[Button]) -> u128 {
     code.iter().fold(0, |value, button| match button {
-        Number(number) => value * 10 + *number as usize,
+        Key(symbol) => symbol
+            .to_digit(10)
+            .map_or(value, |digit| value * 10 + digit as u128),
         _ => value,
     })
 }
 
-fn complexity(code: &[Button], num_robots: usize) -> usize {
+fn complexity(code: &[Button], num_robots: usize) -> u128 {
     numerical_value(code) * shortest_sequence_len(code, num_robots)
 }
 
 #[aoc(day21, part1)]
-fn part1(codes: &[Vec<Button>]) -> usize {
+fn part1(codes: &[Vec<Button>]) -> u128 {
     codes.iter().map(|code| complexity(code, 3)).sum()
 }
 
 #[aoc(day21, part2)]
-fn part2(codes: &[Vec<Button>]) -> usize {
+fn part2(codes: &[Vec<Button>]) -> u128 {
     codes.iter().map(|code| complexity(code, 25 + 1)).sum()
 }
 
+const PROOF_LEVELS: usize = 64;
+
+type Pair = (Button, Button);
+
+struct Matrix(Vec<Vec<BigUint>>);
+
+impl Matrix {
+    fn identity(size: usize) -> Self {
+        Matrix(
+            (0..size)
+                .map(|row| {
+                    (0..size)
+                        .map(|column| {
+                            if row == column {
+                                BigUint::one()
+                            } else {
+                                BigUint::zero()
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix(
+            self.0
+                .iter()
+                .map(|row| {
+                    (0..other.0.len())
+                        .map(|column| {
+                            row.iter()
+                                .zip(other.0.iter())
+                                .filter(|(value, _)| !value.is_zero())
+                                .map(|(value, other_row)| value * &other_row[column])
+                                .sum()
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn pow(&self, mut exponent: u64) -> Matrix {
+        let mut result = Matrix::identity(self.0.len());
+        let mut base = Matrix(self.0.clone());
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
+            }
+
+            exponent >>= 1;
+
+            if exponent > 0 {
+                base = base.multiply(&base);
+            }
+        }
+
+        result
+    }
+
+    fn apply(&self, vector: &[BigUint]) -> Vec<BigUint> {
+        self.0
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(vector)
+                    .map(|(value, cost)| value * cost)
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+fn transitions(path: &[Button], indexes: &HashMap<Pair, usize>) -> Option<Vec<usize>> {
+    path.iter()
+        .scan(Activate, |prev_button, button| {
+            let pair = (*prev_button, *button);
+            *prev_button = *button;
+            Some(indexes.get(&pair).copied())
+        })
+        .collect()
+}
+
+fn big_path_cost(transitions: &[usize], costs: &[BigUint]) -> BigUint {
+    transitions.iter().map(|index| &costs[*index]).sum()
+}
+
+fn cheapest_transitions<'a>(
+    candidates: &'a [Vec<usize>],
+    costs: &[BigUint],
+) -> (usize, &'a [usize]) {
+    candidates
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, transitions)| big_path_cost(transitions, costs))
+        .map(|(choice, transitions)| (choice, transitions.as_slice()))
+        .unwrap()
+}
+
+fn transition_counts(transitions: &[usize], size: usize) -> Vec<BigInt> {
+    let mut counts = vec![BigInt::zero(); size];
+
+    for index in transitions {
+        counts[*index] += 1;
+    }
+
+    counts
+}
+
+fn choices_are_fixed(
+    candidates: &[Vec<Vec<usize>>],
+    chosen: &[(usize, &[usize])],
+    costs: &[BigUint],
+) -> bool {
+    let size = costs.len();
+    let costs: Vec<BigInt> = costs.iter().cloned().map(BigInt::from).collect();
+    let transition: Vec<Vec<BigInt>> = chosen
+        .iter()
+        .map(|(_, transitions)| transition_counts(transitions, size))
+        .collect();
+
+    candidates
+        .iter()
+        .zip(chosen)
+        .all(|(candidates, (choice, chosen))| {
+            let chosen = transition_counts(chosen, size);
+
+            candidates
+                .iter()
+                .enumerate()
+                .filter(|(other_choice, _)| other_choice != choice)
+                .all(|(_, other)| {
+                    let mut difference: Vec<BigInt> = transition_counts(other, size)
+                        .iter()
+                        .zip(&chosen)
+                        .map(|(other, chosen)| other - chosen)
+                        .collect();
+
+                    for _ in 0..PROOF_LEVELS {
+                        if !difference.iter().any(Signed::is_negative) {
+                            return true;
+                        }
+
+                        difference = (0..size)
+                            .map(|column| {
+                                difference
+                                    .iter()
+                                    .zip(&transition)
+                                    .map(|(value, row)| value * &row[column])
+                                    .sum()
+                            })
+                            .collect();
+
+                        let margin: BigInt = difference
+                            .iter()
+                            .zip(&costs)
+                            .map(|(value, cost)| value * cost)
+                            .sum();
+
+                        if margin.is_negative() {
+                            return false;
+                        }
+                    }
+
+                    false
+                })
+        })
+}
+
+pub fn sequence_len_big(
+    code: &[Button],
+    door: &Keypad,
+    robot: &Keypad,
+    robots_count: u64,
+) -> Option<BigUint> {
+    if robots_count == 0 {
+        door.position(&Activate)?;
+
+        return code.iter().try_fold(BigUint::zero(), |cost, button| {
+            door.position(button).map(|_| cost + 1u32)
+        });
+    }
+
+    let pairs: Vec<Pair> = robot
+        .buttons()
+        .flat_map(|start_button| {
+            robot
+                .buttons()
+                .map(move |end_button| (start_button, end_button))
+        })
+        .collect();
+    let indexes: HashMap<Pair, usize> = pairs
+        .iter()
+        .enumerate()
+        .map(|(index, pair)| (*pair, index))
+        .collect();
+
+    let candidates: Vec<Vec<Vec<usize>>> = pairs
+        .iter()
+        .map(|(start_button, end_button)| {
+            shortest_paths(start_button, end_button, robot)
+                .iter()
+                .filter_map(|path| transitions(path, &indexes))
+                .collect()
+        })
+        .collect();
+
+    if candidates.iter().any(Vec::is_empty) {
+        return None;
+    }
+
+    let mut costs = vec![BigUint::one(); pairs.len()];
+    let mut previous_choices = Vec::new();
+    let mut levels_until_proof = 0u64;
+    let mut proof_interval = 1u64;
+    let mut remaining = robots_count - 1;
+
+    while remaining > 0 {
+        let chosen: Vec<(usize, &[usize])> = candidates
+            .iter()
+            .map(|candidates| cheapest_transitions(candidates, &costs))
+            .collect();
+        let choices: Vec<usize> = chosen.iter().map(|(choice, _)| *choice).collect();
+
+        levels_until_proof = levels_until_proof.saturating_sub(1);
+
+        if choices == previous_choices && levels_until_proof == 0 {
+            if choices_are_fixed(&candidates, &chosen, &costs) {
+                let transition = Matrix(
+                    chosen
+                        .iter()
+                        .map(|(_, transitions)| {
+                            let mut row = vec![BigUint::zero(); pairs.len()];
+
+                            for index in transitions.iter() {
+                                row[*index] += 1u32;
+                            }
+
+                            row
+                        })
+                        .collect(),
+                );
+
+                costs = transition.pow(remaining).apply(&costs);
+                break;
+            }
+
+            levels_until_proof = proof_interval;
+            proof_interval *= 2;
+        }
+
+        costs = chosen
+            .iter()
+            .map(|(_, transitions)| big_path_cost(transitions, &costs))
+            .collect();
+        previous_choices = choices;
+        remaining -= 1;
+    }
+
+    code.iter()
+        .try_fold(
+            (BigUint::zero(), Activate),
+            |(cost, prev_button), button| {
+                let cheapest = shortest_paths(&prev_button, button, door)
+                    .iter()
+                    .filter_map(|path| transitions(path, &indexes))
+                    .map(|transitions| big_path_cost(&transitions, &costs))
+                    .min()?;
+
+                Some((cost + cheapest, *button))
+            },
+        )
+        .map(|(cost, _)| cost)
+}
+
+#[aoc(day21, part2, big)]
+fn part2_big(codes: &[Vec<Button>]) -> Option<BigUint> {
+    codes.iter().try_fold(BigUint::zero(), |total, code| {
+        Some(
+            total
+                + BigUint::from(numerical_value(code))
+                    * sequence_len_big(code, &Keypad::numeric(), &Keypad::directional(), 26)?,
+        )
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,4 +735,188 @@ mod tests {
     fn part1_example() {
         assert_eq!(part1(&parse_input(TEST_INPUT)), 126_384);
     }
+
+    fn buttons(sequence: &str) -> Vec<Button> {
+        sequence.chars().map(Button::from).collect()
+    }
+
+    fn render(sequence: &[Button]) -> String {
+        sequence.iter().map(Button::to_string).collect()
+    }
+
+    #[test]
+    fn simulated_sequences() {
+        let typed = simulate(
+            &buttons("<vA<AA>>^AvAA<^A>A<v<A>>^AvA^A<vA>^A<v<A>^A>AAvA^A<v<A>A>^AAAvA<^A>A"),
+            &standard_chain(3),
+        )
+        .unwrap();
+
+        assert_eq!(
+            typed.iter().map(|typed| render(typed)).collect::<Vec<_>>(),
+            vec!["v<<A>>^A<A>AvA<^AA>A<vAAA>^A", "<A^A>^^AvvvA", "029A"]
+        );
+        assert_eq!(
+            simulate(&buttons("<<A"), &standard_chain(2)),
+            Err(RobotPanic::ArmOffKeypad {
+                keypad: 1,
+                position: (0, 0)
+            })
+        );
+
+        for code in parse_input(TEST_INPUT) {
+            for robots_count in 1..=4 {
+                let keypads = standard_chain(robots_count);
+                let sequence = shortest_sequence(&code, &keypads).unwrap();
+
+                assert_eq!(
+                    sequence.len() as u128,
+                    shortest_sequence_len(&code, robots_count)
+                );
+                assert_eq!(simulate(&sequence, &keypads).unwrap().last(), Some(&code));
+            }
+        }
+    }
+
+    #[test]
+    fn parsed_keypads() {
+        let boxed = Keypad::parse(
+            "+---+---+---+
+| 7 | 8 | 9 |
++---+---+---+
+| 4 | 5 | 6 |
++---+---+---+
+| 1 | 2 | 3 |
++---+---+---+
+    | 0 | A |
+    +---+---+",
+        )
+        .unwrap();
+
+        assert_eq!(boxed, Keypad::numeric());
+        assert_eq!(Keypad::parse("12\n21"), Err(KeypadError::DuplicateKey('2')));
+        assert_eq!(Keypad::parse("123"), Err(KeypadError::MissingActivate));
+
+        let phone = Keypad::parse("123\n456\n789\n*0A").unwrap();
+        let alphanumeric = Keypad::parse("ABCDEF\nGHIJKL\nMNOPQR\nSTUVWX\nYZ 01 ").unwrap();
+        let arrows_in_row = Keypad::parse("<^v>A").unwrap();
+
+        let code = buttons("HELLO1A");
+        let keypads = vec![
+            alphanumeric,
+            Keypad::directional(),
+            arrows_in_row,
+            Keypad::directional(),
+        ];
+        let sequence = shortest_sequence(&code, &keypads).unwrap();
+
+        assert_eq!(Some(sequence.len() as u128), sequence_len(&code, &keypads));
+        assert_eq!(simulate(&sequence, &keypads).unwrap().last(), Some(&code));
+
+        let code = buttons("*0A");
+        let keypads = vec![phone, Keypad::directional(), Keypad::directional()];
+
+        assert_eq!(sequence_len(&code, &keypads), Some(17));
+        assert_eq!(sequence_len(&buttons("Z"), &keypads), None);
+        assert_eq!(sequence_len(&code, &[]), None);
+        assert_eq!(shortest_sequence(&code, &[]), None);
+        assert_eq!(simulate(&code, &[]), Err(RobotPanic::EmptyChain));
+    }
+
+    #[test]
+    fn thousands_of_robots() {
+        let codes = parse_input(TEST_INPUT);
+        let (numeric, directional) = (Keypad::numeric(), Keypad::directional());
+
+        assert_eq!(part2_big(&codes), Some(BigUint::from(part2(&codes))));
+
+        for code in &codes {
+            for robots_count in [0, 1, 3, 26, 60] {
+                assert_eq!(
+                    sequence_len_big(code, &numeric, &directional, robots_count),
+                    sequence_len(code, &standard_chain(robots_count as usize)).map(BigUint::from)
+                );
+            }
+        }
+
+        assert_eq!(sequence_len(&codes[0], &standard_chain(200)), None);
+
+        let exact = |robots_count: usize| {
+            let mut costs = vec![BigUint::one(); 25];
+
+            let pairs: Vec<Pair> = directional
+                .buttons()
+                .flat_map(|a| directional.buttons().map(move |b| (a, b)))
+                .collect();
+            let indexes: HashMap<Pair, usize> = pairs
+                .iter()
+                .enumerate()
+                .map(|(i, pair)| (*pair, i))
+                .collect();
+
+            for _ in 1..robots_count {
+                costs = pairs
+                    .iter()
+                    .map(|(a, b)| {
+                        shortest_paths(a, b, &directional)
+                            .iter()
+                            .map(|path| {
+                                big_path_cost(&transitions(path, &indexes).unwrap(), &costs)
+                            })
+                            .min()
+                            .unwrap()
+                    })
+                    .collect();
+            }
+
+            let (total, _) = codes[0].iter().fold(
+                (BigUint::zero(), Activate),
+                |(total, prev_button), button| {
+                    let cheapest = shortest_paths(&prev_button, button, &numeric)
+                        .iter()
+                        .map(|path| big_path_cost(&transitions(path, &indexes).unwrap(), &costs))
+                        .min()
+                        .unwrap();
+
+                    (total + cheapest, *button)
+                },
+            );
+
+            total
+        };
+
+        assert_eq!(
+            sequence_len_big(&codes[0], &numeric, &directional, 300),
+            Some(exact(300))
+        );
+
+        let huge = sequence_len_big(&codes[0], &numeric, &directional, 5_000).unwrap();
+
+        assert!(huge.bits() > 5_000);
+    }
+
+    #[test]
+    fn unstable_choices() {
+        let code = buttons("029A");
+        let (numeric, robot) = (Keypad::numeric(), Keypad::parse("^<>\n vA").unwrap());
+
+        for robots_count in 1..=40 {
+            let mut keypads = vec![numeric.clone()];
+            keypads.extend((0..robots_count).map(|_| robot.clone()));
+
+            assert_eq!(
+                sequence_len_big(&code, &numeric, &robot, robots_count as u64),
+                sequence_len(&code, &keypads).map(BigUint::from)
+            );
+        }
+
+        assert_eq!(
+            sequence_len_big(&code, &numeric, &robot, 23),
+            Some(BigUint::from(3_421_477_080u64))
+        );
+        assert_eq!(
+            sequence_len_big(&code, &numeric, &robot, 26),
+            Some(BigUint::from(49_399_015_364u64))
+        );
+    }
 }
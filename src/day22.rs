@@ -1,4 +1,7 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 
 #[aoc_generator(day22)]
 fn parse_input(input: &str) -> Vec<i64> {
@@ -35,40 +38,350 @@ fn part1(secret_numbers: &[i64]) -> i64 {
         .sum()
 }
 
-#[aoc(day22, part2)]
-fn part2(secret_numbers: &[i64]) -> i64 {
-    let mut total_price_per_sequence = vec![0i64; 2usize.pow(20)];
+const SECRET_BITS: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Gf2Matrix {
+    columns: [u32; SECRET_BITS],
+}
+
+impl Gf2Matrix {
+    fn identity() -> Self {
+        let mut columns = [0; SECRET_BITS];
+
+        for (bit, column) in columns.iter_mut().enumerate() {
+            *column = 1 << bit;
+        }
+
+        Gf2Matrix { columns }
+    }
 
-    for secret_number in secret_numbers {
-        let mut secret_number = *secret_number;
-        let mut price = secret_number % 10;
+    fn apply(&self, vector: u32) -> u32 {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| vector >> bit & 1 == 1)
+            .fold(0, |result, (_, column)| result ^ column)
+    }
 
-        let mut sequence = 0;
+    fn multiply(&self, other: &Gf2Matrix) -> Gf2Matrix {
+        let mut columns = [0; SECRET_BITS];
 
-        let mut vec_encountered_sequences = vec![false; 2usize.pow(20)];
+        for (column, other_column) in columns.iter_mut().zip(other.columns.iter()) {
+            *column = self.apply(*other_column);
+        }
 
-        for i in 0..2_000 {
-            let new_secret_number = process(&secret_number);
-            let new_price = new_secret_number % 10;
+        Gf2Matrix { columns }
+    }
 
-            sequence *= 32;
-            sequence += new_price - price + 9;
+    fn pow(&self, mut exponent: u64) -> Gf2Matrix {
+        let mut result = Gf2Matrix::identity();
+        let mut base = *self;
 
-            if i > 3 {
-                sequence &= (2usize.pow(20) - 1) as i64;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
             }
 
-            secret_number = new_secret_number;
-            price = new_price;
+            base = base.multiply(&base);
+            exponent >>= 1;
+        }
+
+        result
+    }
+
+    fn inverse(&self) -> Option<Gf2Matrix> {
+        let mut rows: Vec<(u32, u32)> = (0..SECRET_BITS)
+            .map(|row| {
+                let bits = self
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .fold(0, |bits, (column, values)| {
+                        bits | (values >> row & 1) << column
+                    });
+
+                (bits, 1 << row)
+            })
+            .collect();
+
+        for column in 0..SECRET_BITS {
+            let pivot = (column..SECRET_BITS).find(|row| rows[*row].0 >> column & 1 == 1)?;
+            rows.swap(column, pivot);
 
-            if i >= 3 && !vec_encountered_sequences[sequence as usize] {
-                total_price_per_sequence[sequence as usize] += price;
-                vec_encountered_sequences[sequence as usize] = true;
+            let (pivot_bits, pivot_inverse) = rows[column];
+
+            for (row, (bits, inverse)) in rows.iter_mut().enumerate() {
+                if row != column && *bits >> column & 1 == 1 {
+                    *bits ^= pivot_bits;
+                    *inverse ^= pivot_inverse;
+                }
+            }
+        }
+
+        let mut columns = [0; SECRET_BITS];
+
+        for (row, (_, inverse)) in rows.iter().enumerate() {
+            for (column, values) in columns.iter_mut().enumerate() {
+                *values |= (inverse >> column & 1) << row;
             }
         }
+
+        Some(Gf2Matrix { columns })
+    }
+}
+
+fn prime_factors(mut number: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut factor = 2;
+
+    while factor * factor <= number {
+        if number.is_multiple_of(factor) {
+            factors.push(factor);
+
+            while number.is_multiple_of(factor) {
+                number /= factor;
+            }
+        }
+
+        factor += 1;
+    }
+
+    if number > 1 {
+        factors.push(number);
+    }
+
+    factors
+}
+
+pub struct SecretGenerator {
+    step: Gf2Matrix,
+    inverse_step: Gf2Matrix,
+}
+
+impl Default for SecretGenerator {
+    fn default() -> Self {
+        SecretGenerator::new()
+    }
+}
+
+impl SecretGenerator {
+    pub fn new() -> Self {
+        let mut columns = [0; SECRET_BITS];
+
+        for (bit, column) in columns.iter_mut().enumerate() {
+            *column = process(&(1 << bit)) as u32;
+        }
+
+        let step = Gf2Matrix { columns };
+
+        SecretGenerator {
+            step,
+            inverse_step: step.inverse().unwrap(),
+        }
+    }
+
+    pub fn nth(&self, secret_number: i64, steps: u64) -> i64 {
+        self.step.pow(steps).apply(prune(&secret_number) as u32) as i64
+    }
+
+    pub fn previous(&self, secret_number: i64) -> i64 {
+        self.inverse_step.apply(prune(&secret_number) as u32) as i64
+    }
+
+    fn minimal_period(&self, returns: impl Fn(u64) -> bool) -> Option<u64> {
+        let mut period = (1u64 << SECRET_BITS) - 1;
+
+        if !returns(period) {
+            return None;
+        }
+
+        for factor in prime_factors(period) {
+            while period.is_multiple_of(factor) && returns(period / factor) {
+                period /= factor;
+            }
+        }
+
+        Some(period)
+    }
+
+    pub fn period(&self) -> Option<u64> {
+        self.minimal_period(|steps| self.step.pow(steps) == Gf2Matrix::identity())
+    }
+
+    pub fn cycle_length(&self, secret_number: i64) -> Option<u64> {
+        self.minimal_period(|steps| self.nth(secret_number, steps) == prune(&secret_number))
+    }
+}
+
+#[aoc(day22, part1, matrix)]
+fn part1_matrix(secret_numbers: &[i64]) -> i64 {
+    let generator = SecretGenerator::new();
+
+    secret_numbers
+        .iter()
+        .map(|secret_number| generator.nth(*secret_number, 2_000))
+        .sum()
+}
+
+const CHANGE_BITS: usize = 5;
+const MAX_SEQUENCE_LENGTH: usize = u64::BITS as usize / CHANGE_BITS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketConfig {
+    pub horizon: usize,
+    pub sequence_length: usize,
+}
+
+impl Default for MarketConfig {
+    fn default() -> Self {
+        MarketConfig {
+            horizon: 2_000,
+            sequence_length: 4,
+        }
     }
+}
 
-    *total_price_per_sequence.iter().max().unwrap()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSequenceLength(pub usize);
+
+impl fmt::Display for InvalidSequenceLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sequence length {} is outside 1..={}",
+            self.0, MAX_SEQUENCE_LENGTH
+        )
+    }
+}
+
+impl Error for InvalidSequenceLength {}
+
+fn for_each_sequence(secret_number: i64, config: &MarketConfig, mut visit: impl FnMut(u64, i64)) {
+    let mask = (1u64 << (CHANGE_BITS * config.sequence_length)) - 1;
+
+    let mut secret_number = secret_number;
+    let mut price = secret_number % 10;
+    let mut sequence = 0u64;
+
+    for i in 0..config.horizon {
+        let new_secret_number = process(&secret_number);
+        let new_price = new_secret_number % 10;
+
+        sequence = (sequence << CHANGE_BITS | (new_price - price + 9) as u64) & mask;
+
+        secret_number = new_secret_number;
+        price = new_price;
+
+        if i + 1 >= config.sequence_length {
+            visit(sequence, price);
+        }
+    }
+}
+
+pub fn encode_sequence(changes: &[i64]) -> Option<u64> {
+    if changes.len() > MAX_SEQUENCE_LENGTH {
+        return None;
+    }
+
+    changes.iter().try_fold(0u64, |sequence, change| {
+        (-9..=9)
+            .contains(change)
+            .then_some(sequence << CHANGE_BITS | (change + 9) as u64)
+    })
+}
+
+pub fn decode_sequence(sequence: u64, sequence_length: usize) -> Vec<i64> {
+    (0..sequence_length)
+        .rev()
+        .map(|position| (sequence >> (CHANGE_BITS * position) & 31) as i64 - 9)
+        .collect()
+}
+
+pub struct Market {
+    config: MarketConfig,
+    secret_numbers: Vec<i64>,
+    totals: HashMap<u64, i64>,
+}
+
+impl Market {
+    pub fn new(
+        secret_numbers: &[i64],
+        config: MarketConfig,
+    ) -> Result<Self, InvalidSequenceLength> {
+        if !(1..=MAX_SEQUENCE_LENGTH).contains(&config.sequence_length) {
+            return Err(InvalidSequenceLength(config.sequence_length));
+        }
+
+        let mut totals: HashMap<u64, (i64, usize)> = HashMap::new();
+
+        for (buyer, secret_number) in secret_numbers.iter().enumerate() {
+            for_each_sequence(*secret_number, &config, |sequence, price| {
+                let (total, last_buyer) = totals.entry(sequence).or_insert((0, usize::MAX));
+
+                if *last_buyer != buyer {
+                    *total += price;
+                    *last_buyer = buyer;
+                }
+            });
+        }
+
+        Ok(Market {
+            config,
+            secret_numbers: secret_numbers.to_vec(),
+            totals: totals
+                .into_iter()
+                .map(|(sequence, (total, _))| (sequence, total))
+                .collect(),
+        })
+    }
+
+    pub fn top(&self, count: usize) -> Vec<(Vec<i64>, i64)> {
+        let mut totals: Vec<(u64, i64)> = self.totals.iter().map(|(s, t)| (*s, *t)).collect();
+        totals.sort_unstable_by_key(|(sequence, total)| (-total, *sequence));
+
+        totals
+            .into_iter()
+            .take(count)
+            .map(|(sequence, total)| {
+                (
+                    decode_sequence(sequence, self.config.sequence_length),
+                    total,
+                )
+            })
+            .collect()
+    }
+
+    pub fn prices_for(&self, changes: &[i64]) -> Vec<Option<i64>> {
+        let sequence =
+            encode_sequence(changes).filter(|_| changes.len() == self.config.sequence_length);
+
+        self.secret_numbers
+            .iter()
+            .map(|secret_number| {
+                let mut sold_at = None;
+
+                if let Some(sequence) = sequence {
+                    for_each_sequence(*secret_number, &self.config, |next_sequence, price| {
+                        if next_sequence == sequence && sold_at.is_none() {
+                            sold_at = Some(price);
+                        }
+                    });
+                }
+
+                sold_at
+            })
+            .collect()
+    }
+}
+
+#[aoc(day22, part2)]
+fn part2(secret_numbers: &[i64]) -> i64 {
+    Market::new(secret_numbers, MarketConfig::default())
+        .unwrap()
+        .top(1)
+        .first()
+        .map_or(0, |(_, total)| *total)
 }
 
 #[cfg(test)]
@@ -94,4 +407,87 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse_input(TEST_INPUT_2)), 23);
     }
+
+    #[test]
+    fn matrix_generator() {
+        let generator = SecretGenerator::new();
+
+        assert_eq!(part1_matrix(&parse_input(TEST_INPUT_1)), 37_327_623);
+        assert_eq!(generator.nth(123, 10), 5_908_254);
+
+        for secret_number in [1, 123, 2_024, 16_777_215] {
+            for steps in [0, 1, 7, 2_000] {
+                assert_eq!(
+                    generator.nth(secret_number, steps),
+                    nth_new_secret_number(&secret_number, steps as usize)
+                );
+            }
+
+            assert_eq!(generator.previous(process(&secret_number)), secret_number);
+        }
+
+        assert_eq!(generator.period(), Some(16_777_215));
+        assert_eq!(generator.cycle_length(123), Some(16_777_215));
+        assert_eq!(generator.cycle_length(0), Some(1));
+        assert_eq!(generator.nth(123, 16_777_215 + 5), generator.nth(123, 5));
+    }
+
+    #[test]
+    fn market_api() {
+        let secret_numbers = parse_input(TEST_INPUT_2);
+        let market = Market::new(&secret_numbers, MarketConfig::default()).unwrap();
+
+        assert_eq!(market.top(1), vec![(vec![-2, 1, -1, 3], 23)]);
+        assert_eq!(
+            market.prices_for(&[-2, 1, -1, 3]),
+            vec![Some(7), Some(7), None, Some(9)]
+        );
+        assert_eq!(
+            decode_sequence(encode_sequence(&[-9, 0, 9]).unwrap(), 3),
+            [-9, 0, 9]
+        );
+        assert_eq!(encode_sequence(&[10]), None);
+
+        let short = Market::new(
+            &[123],
+            MarketConfig {
+                horizon: 9,
+                sequence_length: 4,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(short.top(1), vec![(vec![-1, -1, 0, 2], 6)]);
+
+        let pairs = Market::new(
+            &[123],
+            MarketConfig {
+                horizon: 9,
+                sequence_length: 2,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            pairs.top(3),
+            vec![(vec![-3, 6], 6), (vec![0, 2], 6), (vec![6, -1], 5)]
+        );
+        assert_eq!(pairs.prices_for(&[-1, -1]), vec![Some(4)]);
+        assert_eq!(pairs.prices_for(&[-1, -1, 0]), vec![None]);
+
+        let with_length = |sequence_length| {
+            Market::new(
+                &[123],
+                MarketConfig {
+                    horizon: 20,
+                    sequence_length,
+                },
+            )
+        };
+
+        assert_eq!(with_length(0).map(|_| ()), Err(InvalidSequenceLength(0)));
+        assert_eq!(with_length(13).map(|_| ()), Err(InvalidSequenceLength(13)));
+        assert_eq!(with_length(12).unwrap().top(1)[0].0.len(), 12);
+        assert_eq!(encode_sequence(&[0; 13]), None);
+    }
 }
@@ -9,108 +9,262 @@ fn parse_input(input: &str) -> Vec<(String, String)> {
     parser.parse(input).unwrap()
 }
 
-fn connected_computers(connections: &[(String, String)]) -> HashMap<String, HashSet<String>> {
-    connections.iter().fold(
-        HashMap::new(),
-        |mut connected_computers, (computer_1, computer_2)| {
-            connected_computers
-                .entry(computer_1.to_owned())
-                .or_default()
-                .insert(computer_2.to_owned());
-            connected_computers
-                .entry(computer_2.to_owned())
-                .or_default()
-                .insert(computer_1.to_owned());
-
-            connected_computers
-        },
-    )
+pub struct Network {
+    names: Vec<String>,
+    neighbours: Vec<HashSet<usize>>,
 }
 
-#[aoc(day23, part1)]
-fn part1(connections: &[(String, String)]) -> usize {
-    let connected_computers: HashMap<String, HashSet<String>> = connected_computers(connections);
+impl Network {
+    pub fn new<S: AsRef<str>>(connections: &[(S, S)]) -> Self {
+        let mut names: Vec<String> = connections
+            .iter()
+            .flat_map(|(computer_1, computer_2)| [computer_1.as_ref(), computer_2.as_ref()])
+            .map(String::from)
+            .collect();
 
-    connections
-        .iter()
-        .map(|(computer_1, computer_2)| {
-            connected_computers
-                .iter()
-                .filter(|(computer, connected_computers)| {
-                    connected_computers.contains(computer_1)
-                        && connected_computers.contains(computer_2)
-                        && (computer.starts_with('t')
-                            || computer_1.starts_with('t')
-                            || computer_2.starts_with('t'))
-                })
-                .count()
-        })
-        .sum::<usize>()
-        / 3
-}
+        names.sort_unstable();
+        names.dedup();
+
+        let indexes: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.as_str(), index))
+            .collect();
+
+        let mut neighbours = vec![HashSet::new(); names.len()];
 
-fn password(sorted_computers: &[&String]) -> String {
-    let mut password = String::new();
+        for (computer_1, computer_2) in connections {
+            let computer_1 = indexes[computer_1.as_ref()];
+            let computer_2 = indexes[computer_2.as_ref()];
 
-    if !sorted_computers.is_empty() {
-        password.push_str(sorted_computers.first().unwrap());
+            if computer_1 != computer_2 {
+                neighbours[computer_1].insert(computer_2);
+                neighbours[computer_2].insert(computer_1);
+            }
+        }
+
+        Network { names, neighbours }
     }
 
-    sorted_computers.iter().skip(1).for_each(|computer_name| {
-        password.push(',');
-        password.push_str(computer_name);
-    });
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
 
-    password
-}
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
 
-#[aoc(day23, part2)]
-fn part2(connections: &[(String, String)]) -> String {
-    let connected_computers: HashMap<String, HashSet<String>> = connected_computers(connections);
-    let mut groups: Vec<HashSet<String>> = vec![];
-
-    for computer in connected_computers.keys() {
-        if !groups.iter().any(|group| {
-            group.iter().all(|group_computer| {
-                connected_computers
-                    .get(group_computer)
-                    .unwrap()
-                    .contains(computer)
-            })
-        }) {
-            let mut group = HashSet::from([computer.to_owned()]);
-            let mut queue = vec![computer];
-
-            while let Some(computer) = queue.pop() {
-                for connected_computer in connected_computers.get(computer).into_iter().flatten() {
-                    if !group.contains(connected_computer)
-                        && group.iter().all(|group_computer| {
-                            connected_computers
-                                .get(group_computer)
-                                .unwrap()
-                                .contains(connected_computer)
-                        })
-                    {
-                        group.insert(connected_computer.to_owned());
-                        queue.push(connected_computer);
-                    }
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    fn degeneracy_ordering(&self) -> Vec<usize> {
+        let mut degrees: Vec<usize> = self.neighbours.iter().map(HashSet::len).collect();
+        let mut buckets = vec![Vec::new(); degrees.iter().max().map_or(0, |degree| degree + 1)];
+        let mut removed = vec![false; self.len()];
+        let mut ordering = Vec::with_capacity(self.len());
+        let mut lowest = 0;
+
+        for (computer, degree) in degrees.iter().enumerate() {
+            buckets[*degree].push(computer);
+        }
+
+        while ordering.len() < self.len() {
+            let Some(computer) = buckets[lowest].pop() else {
+                lowest += 1;
+                continue;
+            };
+
+            if removed[computer] || degrees[computer] != lowest {
+                continue;
+            }
+
+            removed[computer] = true;
+            ordering.push(computer);
+
+            for neighbour in &self.neighbours[computer] {
+                if !removed[*neighbour] {
+                    degrees[*neighbour] -= 1;
+                    buckets[degrees[*neighbour]].push(*neighbour);
+                    lowest = lowest.min(degrees[*neighbour]);
                 }
             }
+        }
+
+        ordering
+    }
+
+    fn bron_kerbosch(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: HashSet<usize>,
+        mut excluded: HashSet<usize>,
+        visit: &mut impl FnMut(&[usize]),
+    ) {
+        let Some(pivot) = candidates
+            .union(&excluded)
+            .max_by_key(|computer| {
+                candidates
+                    .intersection(&self.neighbours[**computer])
+                    .count()
+            })
+            .copied()
+        else {
+            visit(clique);
+            return;
+        };
+
+        let branches: Vec<usize> = candidates
+            .difference(&self.neighbours[pivot])
+            .copied()
+            .collect();
+
+        for computer in branches {
+            clique.push(computer);
+            self.bron_kerbosch(
+                clique,
+                candidates
+                    .intersection(&self.neighbours[computer])
+                    .copied()
+                    .collect(),
+                excluded
+                    .intersection(&self.neighbours[computer])
+                    .copied()
+                    .collect(),
+                visit,
+            );
+            clique.pop();
+
+            candidates.remove(&computer);
+            excluded.insert(computer);
+        }
+    }
+
+    fn for_each_maximal_clique(&self, mut visit: impl FnMut(&[usize])) {
+        let ordering = self.degeneracy_ordering();
+        let mut positions = vec![0; self.len()];
+
+        for (position, computer) in ordering.iter().enumerate() {
+            positions[*computer] = position;
+        }
+
+        for (position, computer) in ordering.iter().enumerate() {
+            let (candidates, excluded) = self.neighbours[*computer]
+                .iter()
+                .partition(|neighbour| positions[**neighbour] > position);
 
-            groups.push(group);
+            self.bron_kerbosch(&mut vec![*computer], candidates, excluded, &mut visit);
         }
     }
 
-    let mut computers = groups
-        .iter()
-        .max_by(|component1, component2| component1.len().cmp(&component2.len()))
-        .unwrap()
-        .iter()
-        .collect::<Vec<_>>();
+    fn clique_names(&self, clique: &[usize]) -> Vec<&str> {
+        let mut names: Vec<&str> = clique
+            .iter()
+            .map(|computer| self.names[*computer].as_str())
+            .collect();
 
-    computers.sort_unstable();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn maximal_cliques(&self) -> Vec<Vec<&str>> {
+        let mut cliques = Vec::new();
+
+        self.for_each_maximal_clique(|clique| cliques.push(self.clique_names(clique)));
+
+        cliques.sort_unstable();
+        cliques
+    }
 
-    password(&computers)
+    pub fn maximum_clique(&self) -> Vec<&str> {
+        let mut maximum: Vec<&str> = Vec::new();
+
+        self.for_each_maximal_clique(|clique| {
+            if clique.len() < maximum.len() {
+                return;
+            }
+
+            let names = self.clique_names(clique);
+
+            if names.len() > maximum.len() || names < maximum {
+                maximum = names;
+            }
+        });
+
+        maximum
+    }
+
+    pub fn count_cliques(&self, size: usize, filter: impl Fn(&str) -> bool) -> usize {
+        if size == 0 {
+            return 0;
+        }
+
+        let ordering = self.degeneracy_ordering();
+        let mut positions = vec![0; self.len()];
+
+        for (position, computer) in ordering.iter().enumerate() {
+            positions[*computer] = position;
+        }
+
+        let later: Vec<HashSet<usize>> = self
+            .neighbours
+            .iter()
+            .enumerate()
+            .map(|(computer, neighbours)| {
+                neighbours
+                    .iter()
+                    .filter(|neighbour| positions[**neighbour] > positions[computer])
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        (0..self.len())
+            .map(|computer| {
+                self.count_extensions(&later, &mut vec![computer], &later[computer], size, &filter)
+            })
+            .sum()
+    }
+
+    fn count_extensions(
+        &self,
+        later: &[HashSet<usize>],
+        clique: &mut Vec<usize>,
+        candidates: &HashSet<usize>,
+        size: usize,
+        filter: &impl Fn(&str) -> bool,
+    ) -> usize {
+        if clique.len() == size {
+            return usize::from(clique.iter().any(|computer| filter(&self.names[*computer])));
+        }
+
+        let mut count = 0;
+
+        for computer in candidates {
+            let remaining: HashSet<usize> = candidates
+                .intersection(&later[*computer])
+                .copied()
+                .collect();
+
+            if clique.len() + 1 + remaining.len() >= size {
+                clique.push(*computer);
+                count += self.count_extensions(later, clique, &remaining, size, filter);
+                clique.pop();
+            }
+        }
+
+        count
+    }
+}
+
+#[aoc(day23, part1)]
+fn part1(connections: &[(String, String)]) -> usize {
+    Network::new(connections).count_cliques(3, |computer| computer.starts_with('t'))
+}
+
+#[aoc(day23, part2)]
+fn part2(connections: &[(String, String)]) -> String {
+    Network::new(connections).maximum_clique().join(",")
 }
 
 #[cfg(test)]
@@ -159,4 +313,45 @@ td-yn";
     fn part2_example() {
         assert_eq!(part2(&parse_input(TEST_INPUT)), "co,de,ka,ta".to_string());
     }
+
+    #[test]
+    fn network_cliques() {
+        let network = Network::new(&parse_input(TEST_INPUT));
+
+        assert_eq!(network.len(), 16);
+        assert_eq!(network.maximal_cliques().len(), 15);
+        assert!(network
+            .maximal_cliques()
+            .contains(&vec!["co", "de", "ka", "ta"]));
+        assert!(network.maximal_cliques().contains(&vec!["cg", "de"]));
+        assert_eq!(network.maximum_clique(), ["co", "de", "ka", "ta"]);
+
+        assert_eq!(network.count_cliques(1, |_| true), 16);
+        assert_eq!(network.count_cliques(2, |_| true), 32);
+        assert_eq!(network.count_cliques(3, |_| true), 12);
+        assert_eq!(network.count_cliques(4, |_| true), 1);
+        assert_eq!(network.count_cliques(5, |_| true), 0);
+        assert_eq!(
+            network.count_cliques(2, |computer| computer.starts_with('t')),
+            15
+        );
+        assert_eq!(network.count_cliques(3, |computer| computer == "co"), 3);
+    }
+
+    #[test]
+    fn degeneracy_ordering() {
+        let network = Network::new(&[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d"), ("d", "e")]);
+        let ordering = network.degeneracy_ordering();
+        let names: Vec<&str> = ordering
+            .iter()
+            .map(|computer| network.names()[*computer].as_str())
+            .collect();
+
+        assert_eq!(names[..2], ["e", "d"]);
+        assert_eq!(
+            names[2..].iter().collect::<HashSet<_>>(),
+            HashSet::from([&"a", &"b", &"c"])
+        );
+        assert_eq!(ordering.iter().collect::<HashSet<_>>().len(), network.len());
+    }
 }
@@ -0,0 +1,46 @@
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSet {
+    pub fn new(size: usize) -> Self {
+        DisjointSet {
+            parents: (0..size).collect(),
+            sizes: vec![1; size],
+        }
+    }
+
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        let mut element = element;
+
+        while self.parents[element] != root {
+            let parent = self.parents[element];
+            self.parents[element] = root;
+            element = parent;
+        }
+
+        root
+    }
+
+    pub fn union(&mut self, first: usize, second: usize) {
+        let (mut first, mut second) = (self.find(first), self.find(second));
+
+        if first == second {
+            return;
+        }
+
+        if self.sizes[first] < self.sizes[second] {
+            (first, second) = (second, first);
+        }
+
+        self.parents[second] = first;
+        self.sizes[first] += self.sizes[second];
+    }
+}
@@ -0,0 +1,668 @@
+use num::Zero;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Add;
+
+pub trait SearchProblem {
+    type State: Clone + Eq + Hash;
+    type Cost: Copy + Ord + Add<Output = Self::Cost> + Zero;
+
+    fn starts(&self) -> Vec<Self::State>;
+
+    fn successors(&self, state: &Self::State) -> Vec<(Self::State, Self::Cost)>;
+
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    fn heuristic(&self, _state: &Self::State) -> Self::Cost {
+        Self::Cost::zero()
+    }
+}
+
+pub trait ReversibleProblem: SearchProblem {
+    fn goals(&self) -> Vec<Self::State>;
+
+    fn predecessors(&self, state: &Self::State) -> Vec<(Self::State, Self::Cost)>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<S, C> {
+    pub states: Vec<S>,
+    pub cost: C,
+}
+
+struct FrontierEntry<S, C> {
+    state: S,
+    cost: C,
+    priority: C,
+}
+
+impl<S, C: Ord> Ord for FrontierEntry<S, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+    }
+}
+
+impl<S, C: Ord> PartialOrd for FrontierEntry<S, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S, C: Ord> PartialEq for FrontierEntry<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<S, C: Ord> Eq for FrontierEntry<S, C> {}
+
+fn reconstruct<S: Clone + Eq + Hash>(parents: &HashMap<S, S>, last: &S) -> Vec<S> {
+    let mut states = vec![last.clone()];
+
+    while let Some(parent) = parents.get(states.last().unwrap()) {
+        states.push(parent.clone());
+    }
+
+    states.reverse();
+    states
+}
+
+pub fn bfs<P: SearchProblem>(problem: &P) -> Option<Path<P::State, P::Cost>> {
+    let mut parents: HashMap<P::State, P::State> = HashMap::new();
+    let mut costs: HashMap<P::State, P::Cost> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for start in problem.starts() {
+        if !costs.contains_key(&start) {
+            costs.insert(start.clone(), P::Cost::zero());
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        let cost = costs[&state];
+
+        if problem.is_goal(&state) {
+            return Some(Path {
+                states: reconstruct(&parents, &state),
+                cost,
+            });
+        }
+
+        for (next_state, step_cost) in problem.successors(&state) {
+            if !costs.contains_key(&next_state) {
+                costs.insert(next_state.clone(), cost + step_cost);
+                parents.insert(next_state.clone(), state.clone());
+                queue.push_back(next_state);
+            }
+        }
+    }
+
+    None
+}
+
+fn best_first<P: SearchProblem>(
+    problem: &P,
+    with_heuristic: bool,
+) -> Option<Path<P::State, P::Cost>> {
+    let priority = |state: &P::State, cost: P::Cost| {
+        if with_heuristic {
+            cost + problem.heuristic(state)
+        } else {
+            cost
+        }
+    };
+
+    let mut parents: HashMap<P::State, P::State> = HashMap::new();
+    let mut min_costs: HashMap<P::State, P::Cost> = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+
+    for start in problem.starts() {
+        min_costs.insert(start.clone(), P::Cost::zero());
+        frontier.push(FrontierEntry {
+            priority: priority(&start, P::Cost::zero()),
+            state: start,
+            cost: P::Cost::zero(),
+        });
+    }
+
+    while let Some(FrontierEntry { state, cost, .. }) = frontier.pop() {
+        if cost > min_costs[&state] {
+            continue;
+        }
+
+        if problem.is_goal(&state) {
+            return Some(Path {
+                states: reconstruct(&parents, &state),
+                cost,
+            });
+        }
+
+        for (next_state, step_cost) in problem.successors(&state) {
+            let next_cost = cost + step_cost;
+
+            if min_costs
+                .get(&next_state)
+                .is_none_or(|min_cost| next_cost < *min_cost)
+            {
+                min_costs.insert(next_state.clone(), next_cost);
+                parents.insert(next_state.clone(), state.clone());
+                frontier.push(FrontierEntry {
+                    priority: priority(&next_state, next_cost),
+                    state: next_state,
+                    cost: next_cost,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+pub fn dijkstra<P: SearchProblem>(problem: &P) -> Option<Path<P::State, P::Cost>> {
+    best_first(problem, false)
+}
+
+pub fn astar<P: SearchProblem>(problem: &P) -> Option<Path<P::State, P::Cost>> {
+    best_first(problem, true)
+}
+
+pub fn distances<P: SearchProblem>(problem: &P) -> HashMap<P::State, P::Cost> {
+    let mut min_costs: HashMap<P::State, P::Cost> = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+
+    for start in problem.starts() {
+        min_costs.insert(start.clone(), P::Cost::zero());
+        frontier.push(FrontierEntry {
+            state: start,
+            cost: P::Cost::zero(),
+            priority: P::Cost::zero(),
+        });
+    }
+
+    while let Some(FrontierEntry { state, cost, .. }) = frontier.pop() {
+        if cost > min_costs[&state] {
+            continue;
+        }
+
+        for (next_state, step_cost) in problem.successors(&state) {
+            let next_cost = cost + step_cost;
+
+            if min_costs
+                .get(&next_state)
+                .is_none_or(|min_cost| next_cost < *min_cost)
+            {
+                min_costs.insert(next_state.clone(), next_cost);
+                frontier.push(FrontierEntry {
+                    state: next_state,
+                    cost: next_cost,
+                    priority: next_cost,
+                });
+            }
+        }
+    }
+
+    min_costs
+}
+
+struct Reversed<'a, P>(&'a P);
+
+impl<P: ReversibleProblem> SearchProblem for Reversed<'_, P> {
+    type State = P::State;
+    type Cost = P::Cost;
+
+    fn starts(&self) -> Vec<Self::State> {
+        self.0.goals()
+    }
+
+    fn successors(&self, state: &Self::State) -> Vec<(Self::State, Self::Cost)> {
+        self.0.predecessors(state)
+    }
+
+    fn is_goal(&self, state: &Self::State) -> bool {
+        self.0.starts().contains(state)
+    }
+}
+
+pub fn distances_to_goals<P: ReversibleProblem>(problem: &P) -> HashMap<P::State, P::Cost> {
+    distances(&Reversed(problem))
+}
+
+struct SearchSide<S, C> {
+    min_costs: HashMap<S, C>,
+    parents: HashMap<S, S>,
+    frontier: BinaryHeap<FrontierEntry<S, C>>,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy + Ord + Add<Output = C> + Zero> SearchSide<S, C> {
+    fn new(origins: Vec<S>) -> Self {
+        let mut side = SearchSide {
+            min_costs: HashMap::new(),
+            parents: HashMap::new(),
+            frontier: BinaryHeap::new(),
+        };
+
+        for origin in origins {
+            side.min_costs.insert(origin.clone(), C::zero());
+            side.frontier.push(FrontierEntry {
+                state: origin,
+                cost: C::zero(),
+                priority: C::zero(),
+            });
+        }
+
+        side
+    }
+
+    fn top_cost(&self) -> Option<C> {
+        self.frontier.peek().map(|entry| entry.cost)
+    }
+
+    fn expand(
+        &mut self,
+        neighbours: impl Fn(&S) -> Vec<(S, C)>,
+        other: &SearchSide<S, C>,
+        best: &mut Option<(C, S)>,
+    ) {
+        let Some(FrontierEntry { state, cost, .. }) = self.frontier.pop() else {
+            return;
+        };
+
+        if cost > self.min_costs[&state] {
+            return;
+        }
+
+        for (next_state, step_cost) in neighbours(&state) {
+            let next_cost = cost + step_cost;
+
+            if self
+                .min_costs
+                .get(&next_state)
+                .is_none_or(|min_cost| next_cost < *min_cost)
+            {
+                self.min_costs.insert(next_state.clone(), next_cost);
+                self.parents.insert(next_state.clone(), state.clone());
+
+                if let Some(other_cost) = other.min_costs.get(&next_state) {
+                    let total = next_cost + *other_cost;
+
+                    if best
+                        .as_ref()
+                        .is_none_or(|(best_cost, _)| total < *best_cost)
+                    {
+                        *best = Some((total, next_state.clone()));
+                    }
+                }
+
+                self.frontier.push(FrontierEntry {
+                    state: next_state,
+                    cost: next_cost,
+                    priority: next_cost,
+                });
+            }
+        }
+    }
+}
+
+pub fn bidirectional_dijkstra<P: ReversibleProblem>(
+    problem: &P,
+) -> Option<Path<P::State, P::Cost>> {
+    let mut forward = SearchSide::new(problem.starts());
+    let mut backward = SearchSide::new(problem.goals());
+
+    let mut best: Option<(P::Cost, P::State)> = forward
+        .min_costs
+        .keys()
+        .find(|state| backward.min_costs.contains_key(*state))
+        .map(|state| (P::Cost::zero(), state.clone()));
+
+    while let (Some(forward_top), Some(backward_top)) = (forward.top_cost(), backward.top_cost()) {
+        if best
+            .as_ref()
+            .is_some_and(|(best_cost, _)| forward_top + backward_top >= *best_cost)
+        {
+            break;
+        }
+
+        if forward_top <= backward_top {
+            forward.expand(|state| problem.successors(state), &backward, &mut best);
+        } else {
+            backward.expand(|state| problem.predecessors(state), &forward, &mut best);
+        }
+    }
+
+    let (cost, meeting_state) = best?;
+
+    let mut states = reconstruct(&forward.parents, &meeting_state);
+    let mut backward_states = reconstruct(&backward.parents, &meeting_state);
+    backward_states.pop();
+    states.extend(backward_states.into_iter().rev());
+
+    Some(Path { states, cost })
+}
+
+#[derive(Debug, Clone)]
+pub struct OptimalPaths<S, C> {
+    pub cost: C,
+    pub goals: Vec<S>,
+    predecessors: HashMap<S, Vec<S>>,
+    path_counts: HashMap<S, Option<u128>>,
+}
+
+impl<S: Clone + Eq + Hash, C> OptimalPaths<S, C> {
+    pub fn path_count(&self) -> Option<u128> {
+        self.goals.iter().try_fold(0u128, |total, goal| {
+            total.checked_add(self.path_counts[goal]?)
+        })
+    }
+
+    pub fn states(&self) -> HashSet<S> {
+        let mut visited: HashSet<S> = self.goals.iter().cloned().collect();
+        let mut stack = self.goals.clone();
+
+        while let Some(state) = stack.pop() {
+            for predecessor in self.predecessors.get(&state).into_iter().flatten() {
+                if visited.insert(predecessor.clone()) {
+                    stack.push(predecessor.clone());
+                }
+            }
+        }
+
+        visited
+    }
+
+    pub fn paths(&self) -> Vec<Vec<S>> {
+        let mut paths = Vec::new();
+        let mut stack: Vec<Vec<S>> = self.goals.iter().map(|goal| vec![goal.clone()]).collect();
+
+        while let Some(reversed_path) = stack.pop() {
+            match self.predecessors.get(reversed_path.last().unwrap()) {
+                Some(predecessors) => {
+                    for predecessor in predecessors {
+                        let mut next_path = reversed_path.clone();
+                        next_path.push(predecessor.clone());
+                        stack.push(next_path);
+                    }
+                }
+                None => paths.push(reversed_path.into_iter().rev().collect()),
+            }
+        }
+
+        paths
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonPositiveStep;
+
+impl fmt::Display for NonPositiveStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "optimal path enumeration requires positive step costs")
+    }
+}
+
+impl Error for NonPositiveStep {}
+
+type OptimalPathsResult<P> = Result<
+    Option<OptimalPaths<<P as SearchProblem>::State, <P as SearchProblem>::Cost>>,
+    NonPositiveStep,
+>;
+
+pub fn all_optimal_paths<P: SearchProblem>(problem: &P) -> OptimalPathsResult<P> {
+    let mut min_costs: HashMap<P::State, P::Cost> = HashMap::new();
+    let mut predecessors: HashMap<P::State, Vec<P::State>> = HashMap::new();
+    let mut path_counts: HashMap<P::State, Option<u128>> = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+
+    for start in problem.starts() {
+        min_costs.insert(start.clone(), P::Cost::zero());
+        path_counts.insert(start.clone(), Some(1));
+        frontier.push(FrontierEntry {
+            state: start,
+            cost: P::Cost::zero(),
+            priority: P::Cost::zero(),
+        });
+    }
+
+    let mut final_cost: Option<P::Cost> = None;
+    let mut goals = Vec::new();
+
+    while let Some(FrontierEntry { state, cost, .. }) = frontier.pop() {
+        if cost > min_costs[&state] {
+            continue;
+        }
+
+        if final_cost.is_some_and(|final_cost| cost > final_cost) {
+            break;
+        }
+
+        if problem.is_goal(&state) {
+            final_cost = Some(cost);
+            goals.push(state);
+            continue;
+        }
+
+        let path_count = path_counts[&state];
+
+        for (next_state, step_cost) in problem.successors(&state) {
+            if step_cost <= P::Cost::zero() {
+                return Err(NonPositiveStep);
+            }
+
+            let next_cost = cost + step_cost;
+
+            match min_costs.get(&next_state) {
+                Some(min_cost) if *min_cost < next_cost => {}
+                Some(min_cost) if *min_cost == next_cost => {
+                    predecessors
+                        .entry(next_state.clone())
+                        .or_default()
+                        .push(state.clone());
+                    let next_count = path_counts.entry(next_state).or_insert(Some(0));
+                    *next_count = next_count
+                        .zip(path_count)
+                        .and_then(|(left, right)| left.checked_add(right));
+                }
+                _ => {
+                    min_costs.insert(next_state.clone(), next_cost);
+                    predecessors.insert(next_state.clone(), vec![state.clone()]);
+                    path_counts.insert(next_state.clone(), path_count);
+                    frontier.push(FrontierEntry {
+                        state: next_state,
+                        cost: next_cost,
+                        priority: next_cost,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(final_cost.map(|cost| OptimalPaths {
+        cost,
+        goals,
+        predecessors,
+        path_counts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Grid {
+        width: i32,
+        height: i32,
+        walls: HashSet<(i32, i32)>,
+    }
+
+    impl Grid {
+        fn neighbours(&self, &(x, y): &(i32, i32)) -> Vec<((i32, i32), u32)> {
+            [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(|(x, y)| *x >= 0 && *x < self.width && *y >= 0 && *y < self.height)
+                .filter(|position| !self.walls.contains(position))
+                .map(|position| (position, 1 + position.1 as u32))
+                .collect()
+        }
+    }
+
+    impl SearchProblem for Grid {
+        type State = (i32, i32);
+        type Cost = u32;
+
+        fn starts(&self) -> Vec<(i32, i32)> {
+            vec![(0, 0)]
+        }
+
+        fn successors(&self, state: &(i32, i32)) -> Vec<((i32, i32), u32)> {
+            self.neighbours(state)
+        }
+
+        fn is_goal(&self, state: &(i32, i32)) -> bool {
+            *state == (self.width - 1, 0)
+        }
+
+        fn heuristic(&self, state: &(i32, i32)) -> u32 {
+            (self.width - 1 - state.0) as u32
+        }
+    }
+
+    impl ReversibleProblem for Grid {
+        fn goals(&self) -> Vec<(i32, i32)> {
+            vec![(self.width - 1, 0)]
+        }
+
+        fn predecessors(&self, state: &(i32, i32)) -> Vec<((i32, i32), u32)> {
+            self.neighbours(state)
+                .into_iter()
+                .map(|(position, _)| (position, 1 + state.1 as u32))
+                .collect()
+        }
+    }
+
+    struct Loop {
+        step_cost: u32,
+    }
+
+    impl SearchProblem for Loop {
+        type State = u32;
+        type Cost = u32;
+
+        fn starts(&self) -> Vec<u32> {
+            vec![0]
+        }
+
+        fn successors(&self, state: &u32) -> Vec<(u32, u32)> {
+            vec![((state + 1) % 3, self.step_cost), (3, 2)]
+        }
+
+        fn is_goal(&self, state: &u32) -> bool {
+            *state == 3
+        }
+    }
+
+    struct Lattice {
+        size: u32,
+    }
+
+    impl SearchProblem for Lattice {
+        type State = (u32, u32);
+        type Cost = u32;
+
+        fn starts(&self) -> Vec<(u32, u32)> {
+            vec![(0, 0)]
+        }
+
+        fn successors(&self, &(x, y): &(u32, u32)) -> Vec<((u32, u32), u32)> {
+            [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|(x, y)| *x <= self.size && *y <= self.size)
+                .map(|position| (position, 1))
+                .collect()
+        }
+
+        fn is_goal(&self, state: &(u32, u32)) -> bool {
+            *state == (self.size, self.size)
+        }
+    }
+
+    fn grid() -> Grid {
+        Grid {
+            width: 5,
+            height: 4,
+            walls: HashSet::from([(2, 0), (2, 1)]),
+        }
+    }
+
+    #[test]
+    fn searches_agree_on_cost() {
+        let grid = grid();
+
+        assert_eq!(bfs(&grid).map(|path| path.states.len()), Some(9));
+        assert_eq!(dijkstra(&grid).map(|path| path.cost), Some(16));
+        assert_eq!(astar(&grid).map(|path| path.cost), Some(16));
+
+        let path = bidirectional_dijkstra(&grid).unwrap();
+
+        assert_eq!(path.cost, 16);
+        assert_eq!(path.states.first(), Some(&(0, 0)));
+        assert_eq!(path.states.last(), Some(&(4, 0)));
+
+        let from_start = distances(&grid);
+        let to_goal = distances_to_goals(&grid);
+
+        assert_eq!(from_start[&(4, 0)], 16);
+        assert_eq!(to_goal[&(0, 0)], 16);
+        assert!(from_start
+            .iter()
+            .all(|(state, cost)| cost + to_goal[state] >= 16));
+    }
+
+    #[test]
+    fn all_optimal_paths_are_enumerated() {
+        let optimal_paths = all_optimal_paths(&grid()).unwrap().unwrap();
+
+        assert_eq!(optimal_paths.cost, 16);
+        assert_eq!(optimal_paths.path_count(), Some(1));
+        assert_eq!(optimal_paths.paths().len(), 1);
+        assert!(!optimal_paths.states().contains(&(2, 3)));
+
+        let open = Grid {
+            width: 3,
+            height: 1,
+            walls: HashSet::new(),
+        };
+
+        assert_eq!(
+            all_optimal_paths(&open).unwrap().unwrap().paths(),
+            vec![vec![(0, 0), (1, 0), (2, 0)]]
+        );
+
+        let free = Loop { step_cost: 0 };
+        let uphill = Loop { step_cost: 1 };
+
+        assert_eq!(all_optimal_paths(&free).map(|_| ()), Err(NonPositiveStep));
+        assert_eq!(
+            all_optimal_paths(&uphill).unwrap().unwrap().path_count(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn path_counts_overflow() {
+        let central_binomial = (1..=60u128).fold(1, |count, k| count * (60 + k) / k);
+        let fitting = all_optimal_paths(&Lattice { size: 60 }).unwrap().unwrap();
+
+        assert_eq!(fitting.cost, 120);
+        assert_eq!(fitting.path_count(), Some(central_binomial));
+
+        let overflowing = all_optimal_paths(&Lattice { size: 70 }).unwrap().unwrap();
+
+        assert_eq!(overflowing.cost, 140);
+        assert_eq!(overflowing.path_count(), None);
+        assert!(overflowing.states().contains(&(35, 35)));
+    }
+}